@@ -1,11 +1,26 @@
-use crate::crafter;
-use crate::ui::ui;
-use crate::utils::{self, Config};
-
-use crossterm::event::{self, Event, KeyCode};
+use crate::config::{
+    self, Config, Conflict, Keys, Notice, Notifications, Profile, Selection, Source, Store,
+};
+use crate::control::{self, Events, Handler};
+use crate::crafter::{self, Kind, Message, Step};
+use crate::hooks::Runner;
+use crate::notification::Notifier;
+use crate::persist::Backup;
+use crate::progress::{Estimate, Progress, Status};
+use crate::share;
+use crate::status_file::Exporter;
+use crate::theme::Theme;
+use crate::ui::{self, ui, Areas};
+use crate::utils;
+use crate::view::{self, Line};
+
+use chrono::{DateTime, Local};
+
+use crossterm::event::{self, Event, KeyCode, MouseButton, MouseEventKind};
 
 use std::{
-    fs, io,
+    collections::VecDeque,
+    io,
     path::PathBuf,
     sync::{
         atomic::{AtomicBool, Ordering},
@@ -20,80 +35,122 @@ use tui::{backend::Backend, Terminal};
 /// XIVCrafter tick rate
 pub const TICK_RATE: Duration = Duration::from_millis(250);
 
+/// number of crafter events kept in the activity log
+pub const LOG_SIZE: usize = 500;
+
+/// lines scrolled by the page up and page down keys
+const CONFIG_PAGE: u16 = 10;
+
+/// Quit is the state of quitting while the crafter is running
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Quit {
+    /// asking how to quit
+    Confirm,
+    /// waiting for the current craft to finish
+    AfterCraft,
+    /// waiting for the crafter to close the crafting log
+    AfterClose,
+}
+
 #[derive(Clone)]
 pub struct App<'a> {
     pub tabs: Vec<&'a str>,
     pub index: usize,
+    pub keys: Keys,
+    pub help: bool,
+    pub compact: bool,
+    pub theme: Theme,
+    pub quit: Option<Quit>,
 
     //// XIVCrafter
     // Settings
-    pub config: PathBuf,
-    pub name: String,
-    pub last_used: i32,
-
-    // Program Settings
-    pub current_amount: i32,
-    pub max_amount: i32,
-    pub start_pause: String,
-    pub stop: String,
-
-    // Consumables
-    pub food: String,
-    pub food_count: i32,
-    pub food_duration: i64,
-    pub potion: String,
-    pub potion_count: i32,
-
-    // In-Game Hotkeys
-    pub confirm: String,
-    pub cancel: String,
-    pub macro1: String,
-    pub macro1_duration: u64,
-    pub macro2: String,
-    pub macro2_duration: u64,
-    pub macro3: String,
-    pub macro3_duration: u64,
+    pub store: Store,
+    pub selection: Selection,
+    pub profile: Config,
+    pub view: Vec<Line>,
+    pub config_scroll: u16,
+    pub error: Option<String>,
+    pub notifications: Notifications,
+
+    // Profiles
+    pub profiles: Vec<Config>,
+    pub profile_index: usize,
+    pub profile_status: Option<String>,
+    pub share: Option<String>,
+    pub input: Option<String>,
+
+    // Backups
+    pub backups: Vec<Backup>,
+    pub backup_index: usize,
+    pub backup_status: Option<String>,
+
+    // Progress
+    pub progress: Progress,
+
+    // Macro
+    pub step: Option<(Step, DateTime<Local>)>,
+
+    // Activity Log
+    pub log: VecDeque<Message>,
+    pub log_filter: Option<Kind>,
+    pub log_scroll: usize,
 }
 
 impl<'a> App<'a> {
-    pub fn init(path: PathBuf) -> App<'a> {
-        let file = fs::read_to_string(&path).expect("Unable to read file");
-        let json: serde_json::Value = serde_json::from_str(&file).expect("Unable to parse JSON");
-        let configs: Vec<Config> = serde_json::from_value(json).unwrap();
-
-        App {
+    pub fn init(store: Store, selection: Selection) -> io::Result<App<'a>> {
+        let configs = store.load()?;
+        let profile = selection
+            .select(&configs)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Unable to find profile"))?;
+        let view = view::lines(&store, &configs, profile.id);
+        let settings = store.load_settings().unwrap_or_default();
+        let backups = store.backups().unwrap_or_default();
+        let profile_index = configs.iter().position(|c| c.id == profile.id).unwrap_or(0);
+
+        let mut app = App {
             // TUI
-            tabs: vec!["Home", "Config"],
+            tabs: vec!["Home", "Profiles", "Config"],
             index: 0,
+            keys: settings.keys,
+            help: false,
+            compact: settings.compact.unwrap_or(false),
+            theme: Theme::new(settings.theme.unwrap_or_default()),
+            quit: None,
 
             // Settings
-            config: path,
-            name: configs[0].name.clone(),
-            last_used: configs[0].id,
-            current_amount: 0,
-            max_amount: configs[0].amount,
-
-            // Program Hotkeys
-            start_pause: configs[0].start_pause.clone(),
-            stop: configs[0].stop.clone(),
-
-            // Consumables
-            food: configs[0].food.clone(),
-            food_count: 0,
-            food_duration: configs[0].food_duration,
-            potion: configs[0].potion.clone(),
-            potion_count: 0,
-
-            // In-Game Hotkeys
-            confirm: configs[0].confirm.clone(),
-            cancel: configs[0].cancel.clone(),
-            macro1: configs[0].macro1.clone(),
-            macro1_duration: configs[0].macro1_duration,
-            macro2: configs[0].macro2.clone(),
-            macro2_duration: configs[0].macro2_duration,
-            macro3: configs[0].macro3.clone(),
-            macro3_duration: configs[0].macro3_duration,
-        }
+            store,
+            selection,
+            profile,
+            config_scroll: view::active_line(&view).unwrap_or(0) as u16,
+            view,
+            error: None,
+            notifications: settings.notifications,
+
+            // Profiles
+            profiles: configs,
+            profile_index,
+            profile_status: None,
+            share: None,
+            input: None,
+
+            // Backups
+            backups,
+            backup_index: 0,
+            backup_status: None,
+
+            // Progress
+            progress: Progress::default(),
+
+            // Macro
+            step: None,
+
+            // Activity Log
+            log: VecDeque::with_capacity(LOG_SIZE),
+            log_filter: None,
+            log_scroll: 0,
+        };
+        app.error = app.hotkey_clashes();
+        Ok(app)
     }
 
     pub fn next(&mut self) {
@@ -108,61 +165,375 @@ impl<'a> App<'a> {
         }
     }
 
-    /// update changes app's values to match the config file
+    /// update changes app's values to match the config, keeping the last good
+    /// values when it cannot be read
     pub fn update(&mut self) {
-        let file = fs::read_to_string(self.config.clone()).expect("Unable to read file");
-        let json: serde_json::Value = serde_json::from_str(&file).expect("Unable to parse JSON");
-        let configs: Vec<Config> = serde_json::from_value(json).unwrap();
-
-        let mut index = None;
-        for (i, config) in configs.iter().enumerate() {
-            if config.last_used {
-                index = Some(i);
-                break;
+        match self.store.load() {
+            Ok(configs) => {
+                match self.selection.select(&configs) {
+                    Some(profile) => {
+                        self.profile = profile;
+                        self.error = None;
+                    }
+                    None => self.error = Some(String::from("Unable to find profile")),
+                }
+
+                self.profiles = configs;
+                if self.profile_index >= self.profiles.len() {
+                    self.profile_index = self.profiles.len().saturating_sub(1);
+                }
             }
+            Err(e) => self.error = Some(e.to_string()),
         }
 
-        if let Some(index) = index {
-            // Settings
-            self.name = configs[index].name.clone();
-            self.last_used = configs[index].id;
-            self.max_amount = configs[index].amount;
-
-            // Program Hotkeys
-            self.start_pause = configs[index].start_pause.clone();
-            self.stop = configs[index].stop.clone();
-
-            // Consumables
-            self.food = configs[index].food.clone();
-            self.food_duration = configs[index].food_duration;
-            self.potion = configs[index].potion.clone();
-
-            // In-Game Hotkeys
-            self.macro1 = configs[index].macro1.clone();
-            self.macro1_duration = configs[index].macro1_duration;
-            self.macro2 = configs[index].macro2.clone();
-            self.macro2_duration = configs[index].macro2_duration;
-            self.macro3 = configs[index].macro3.clone();
-            self.macro3_duration = configs[index].macro3_duration;
-            self.confirm = configs[index].confirm.clone();
-            self.cancel = configs[index].cancel.clone();
+        if let Ok(settings) = self.store.load_settings() {
+            self.keys = settings.keys;
+            self.theme = Theme::new(settings.theme.unwrap_or_default());
+            self.notifications = settings.notifications;
+        }
+
+        self.view = view::lines(&self.store, &self.profiles, self.profile.id);
+        if self.error.is_none() {
+            self.error = self.hotkey_clashes();
+        }
+
+        self.backups = self.store.backups().unwrap_or_default();
+        if self.backup_index >= self.backups.len() {
+            self.backup_index = self.backups.len().saturating_sub(1);
+        }
+    }
+
+    /// hotkey_clashes describes the profile's hotkeys that TUI keys keep from firing
+    fn hotkey_clashes(&self) -> Option<String> {
+        let clashes: Vec<String> = self
+            .keys
+            .clashes(&self.profile)
+            .into_iter()
+            .map(|(field, message)| format!("{} {}", field, message))
+            .collect();
+        (!clashes.is_empty()).then(|| {
+            format!(
+                "Hotkeys of \"{}\" will not work: {}",
+                self.profile.name,
+                clashes.join(", ")
+            )
+        })
+    }
+
+    /// check_profile validates the profile before the crafter starts, showing any errors
+    pub fn check_profile(&mut self) -> Result<(), String> {
+        let errors = config::validate(&self.profile);
+        if errors.is_empty() {
+            return Ok(());
+        }
+
+        let errors: Vec<String> = errors
+            .into_iter()
+            .map(|(field, message)| format!("{} {}", field, message))
+            .collect();
+        let error = format!(
+            "Profile \"{}\" is not valid: {}",
+            self.profile.name,
+            errors.join(", ")
+        );
+        self.error = Some(error.clone());
+        Err(error)
+    }
+
+    pub fn next_profile(&mut self) {
+        if self.profile_index + 1 < self.profiles.len() {
+            self.profile_index += 1;
+            self.share = None;
+        }
+    }
+
+    pub fn previous_profile(&mut self) {
+        if self.profile_index > 0 {
+            self.profile_index -= 1;
+            self.share = None;
+        }
+    }
+
+    /// use_profile switches to the selected profile and remembers it for the next start
+    pub fn use_profile(&mut self) {
+        let (id, name) = match self.profiles.get(self.profile_index) {
+            Some(config) => (config.id, config.name.clone()),
+            None => return,
+        };
+
+        self.profile_status = Some(match self.store.set_last_used(id) {
+            Ok(_) => {
+                self.selection = Selection::default();
+                self.update();
+                self.progress.craft_times.clear();
+                self.config_scroll = view::active_line(&self.view).unwrap_or(0) as u16;
+                format!("Using {}", name)
+            }
+            Err(e) => format!("Unable to switch to {}: {}", name, e),
+        });
+    }
+
+    /// set_amount overrides the profile's amount, like --amount on the command line
+    pub fn set_amount(&mut self, amount: i32) {
+        self.selection.amount = Some(amount);
+        self.profile.amount = amount;
+        self.profile.sources.insert("amount", Source::CommandLine);
+    }
+
+    /// status reports the crafter's progress to the control socket
+    pub fn status(&self, state: &'static str) -> Status {
+        self.progress.status(&self.profile, state)
+    }
+
+    /// export_profile shows the selected profile as a share string
+    pub fn export_profile(&mut self) {
+        if let Some(config) = self.profiles.get(self.profile_index) {
+            match share::encode(&Profile::share(config)) {
+                Ok(share) => {
+                    self.share = Some(share);
+                    self.profile_status = Some(format!("Exported {}", config.name));
+                }
+                Err(e) => {
+                    self.profile_status = Some(format!("Unable to export {}: {}", config.name, e))
+                }
+            }
+        }
+    }
+
+    /// export_profile_file writes the selected profile to a file in the current directory
+    pub fn export_profile_file(&mut self) {
+        if let Some(config) = self.profiles.get(self.profile_index) {
+            let path = PathBuf::from(config::file_name(&config.name, config.id));
+            self.profile_status = Some(match share::write(&Profile::share(config), &path) {
+                Ok(_) => format!("Exported {} to {}", config.name, path.display()),
+                Err(e) => format!("Unable to export {}: {}", config.name, e),
+            });
+        }
+    }
+
+    /// import_profile adds the profile typed or pasted into the input
+    pub fn import_profile(&mut self) {
+        let input = match self.input.take() {
+            Some(input) if !input.trim().is_empty() => input,
+            _ => return,
+        };
+
+        let imported = share::read(&input).and_then(|profile| {
+            let missing = profile.missing_hotkeys();
+            self.store
+                .import(profile, Conflict::Rename)
+                .map(|(config, dropped)| (config, dropped, missing))
+        });
+        self.profile_status = Some(match imported {
+            Ok((config, dropped, missing)) => {
+                self.update();
+                if let Some(index) = self.profiles.iter().position(|c| c.id == config.id) {
+                    self.profile_index = index;
+                }
+                let mut status = format!("Imported {} as profile {}", config.name, config.id);
+                if !dropped.is_empty() {
+                    status.push_str(&format!(", leaving out {}", dropped.join(" and ")));
+                }
+                if !missing.is_empty() {
+                    status.push_str(&format!(
+                        ", using this machine's {} hotkeys",
+                        missing.join(", ")
+                    ));
+                }
+                status
+            }
+            Err(e) => format!("Unable to import: {}", e),
+        });
+    }
+
+    /// log_message records a crafter event and its progress
+    pub fn log_message(&mut self, message: Message) {
+        self.progress.record(&message);
+        self.step = message.step.map(|step| (step, message.time));
+
+        self.log_entry(message);
+    }
+
+    /// log_entry adds an event to the activity log without taking progress from it
+    pub fn log_entry(&mut self, message: Message) {
+        // the crafter repeats its status while paused
+        if let Some(last) = self.log.back() {
+            if last.kind == message.kind && last.text == message.text {
+                return;
+            }
+        }
+
+        // keep the view in place when scrolled back
+        if self.log_scroll > 0 && self.log_filter.is_none_or(|kind| kind == message.kind) {
+            self.log_scroll += 1;
+        }
+
+        if self.log.len() >= LOG_SIZE {
+            self.log.pop_front();
+        }
+        self.log.push_back(message);
+    }
+
+    /// estimate projects the time and consumables needed for the rest of the run
+    pub fn estimate(&self) -> Option<Estimate> {
+        self.progress.estimate(&self.profile)
+    }
+
+    /// log_entries returns the logged events matching the filter, oldest first
+    pub fn log_entries(&self) -> Vec<&Message> {
+        self.log
+            .iter()
+            .filter(|message| self.log_filter.is_none_or(|kind| kind == message.kind))
+            .collect()
+    }
+
+    /// next_log_filter cycles the activity log through each type of event
+    pub fn next_log_filter(&mut self) {
+        self.log_filter = match self.log_filter {
+            None => Some(Kind::ALL[0]),
+            Some(kind) => Kind::ALL
+                .iter()
+                .position(|k| *k == kind)
+                .and_then(|i| Kind::ALL.get(i + 1))
+                .copied(),
+        };
+        self.log_scroll = 0;
+    }
+
+    pub fn scroll_log_up(&mut self) {
+        if self.log_scroll + 1 < self.log_entries().len() {
+            self.log_scroll += 1;
+        }
+    }
+
+    pub fn scroll_log_down(&mut self) {
+        self.log_scroll = self.log_scroll.saturating_sub(1);
+    }
+
+    pub fn scroll_config_up(&mut self, lines: u16) {
+        self.config_scroll = self.config_scroll.saturating_sub(lines);
+    }
+
+    pub fn scroll_config_down(&mut self, lines: u16) {
+        let last = self.view.len().saturating_sub(1) as u16;
+        self.config_scroll = (self.config_scroll + lines).min(last);
+    }
+
+    pub fn next_backup(&mut self) {
+        if self.backup_index + 1 < self.backups.len() {
+            self.backup_index += 1;
+        }
+    }
+
+    pub fn previous_backup(&mut self) {
+        self.backup_index = self.backup_index.saturating_sub(1);
+    }
+
+    /// restore_backup replaces a config file with the selected backup
+    pub fn restore_backup(&mut self) {
+        if let Some(backup) = self.backups.get(self.backup_index) {
+            let name = backup
+                .original
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy();
+            self.backup_status = Some(match self.store.restore(backup) {
+                Ok(_) => format!(
+                    "Restored {} from {}",
+                    name,
+                    backup.time.format("%Y-%m-%d %H:%M:%S")
+                ),
+                Err(e) => format!("Unable to restore {}: {}", name, e),
+            });
         }
     }
 }
 
-pub fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<()> {
-    app.update();
+/// send_profile passes the profile on to the crafter, failing once the crafter has stopped
+fn send_profile(updates: &mpsc::Sender<Config>, profile: &Config) -> Result<(), String> {
+    updates
+        .send(profile.clone())
+        .map_err(|_| String::from("the crafter has stopped"))
+}
+
+/// Requests answers control requests from the TUI's state
+struct Requests<'a, 'b> {
+    app: &'a mut App<'b>,
+    updates: &'a mpsc::Sender<Config>,
+}
+
+impl Handler for Requests<'_, '_> {
+    fn check(&mut self) -> Result<(), String> {
+        self.app.check_profile()
+    }
+
+    fn set_amount(&mut self, amount: i32) -> Result<(), String> {
+        self.app.set_amount(amount);
+        send_profile(self.updates, &self.app.profile)
+    }
 
+    fn status(&self, state: &'static str) -> Status {
+        self.app.status(state)
+    }
+}
+
+pub fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<()> {
     let mut last_tick = Instant::now();
 
     let program_signal = Arc::new(AtomicBool::new(false));
     let crafter_signal = Arc::new(AtomicBool::new(false));
-
-    let receiver = crafter::craft(&app, program_signal.clone(), crafter_signal.clone());
+    let close_signal = Arc::new(AtomicBool::new(false));
+
+    let (updates, config_receiver) = mpsc::channel();
+    let receiver = crafter::craft(
+        app.profile.clone(),
+        config_receiver,
+        program_signal.clone(),
+        crafter_signal.clone(),
+        close_signal.clone(),
+    );
+
+    // reload the config only when it changes
+    let (_watcher, changes) = match app.store.watch() {
+        Ok((watcher, changes)) => (Some(watcher), changes),
+        Err(e) => {
+            app.error = Some(format!("Unable to watch config: {}", e));
+            (None, mpsc::channel().1)
+        }
+    };
+
+    // accept commands from the control socket and the HTTP API
+    let (requests_tx, requests) = mpsc::channel();
+    let events = Events::default();
+    let _server = match control::listen(requests_tx.clone()) {
+        Ok(server) => Some(server),
+        Err(e) => {
+            app.error = Some(format!("Unable to open control socket: {}", e));
+            None
+        }
+    };
+    let settings = app.store.load_settings().unwrap_or_default();
+    if let Err(e) = control::listen_http(&settings, requests_tx, &events) {
+        app.error = Some(format!("Unable to start HTTP API: {}", e));
+    }
+    let mut exporter = settings.status_file.map(Exporter::new);
 
     let mut message = String::from("Waiting...");
-
-    terminal.draw(|f| ui(f, &app, &message, &program_signal, &crafter_signal))?;
+    let mut areas = Areas::default();
+    let mut notifier = Notifier::new(app.notifications.clone());
+    let (mut hooks, hook_output) = Runner::new();
+    let mut crashed = false;
+
+    terminal.draw(|f| {
+        ui(
+            f,
+            &app,
+            &message,
+            &program_signal,
+            &crafter_signal,
+            &mut areas,
+        )
+    })?;
 
     loop {
         // terminal.draw(|f| ui(f, &app, &message, &program_signal, &crafter_signal))?;
@@ -171,54 +542,310 @@ pub fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Resu
             .checked_sub(last_tick.elapsed())
             .unwrap_or_else(|| Duration::from_secs(0));
 
-        let start_pause_hotkey = utils::get_crossterm_key_code(&app.start_pause).unwrap_or(KeyCode::Null);
-        let stop_hotkey = utils::get_crossterm_key_code(&app.stop).unwrap_or(KeyCode::Null);
+        let start_pause_hotkey =
+            utils::get_crossterm_key_code(&app.profile.start_pause).unwrap_or(KeyCode::Null);
+        let stop_hotkey = utils::get_crossterm_key_code(&app.profile.stop).unwrap_or(KeyCode::Null);
 
         if crossterm::event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                if key.code == KeyCode::Char('q') {
-                    program_signal.store(false, Ordering::Relaxed);
-                    crafter_signal.store(false, Ordering::Relaxed);
-                    return Ok(());
-                } else if key.code == KeyCode::Right {
+            let event = event::read()?;
+
+            if app.input.is_some() {
+                // Import Input
+                let input = app.input.as_mut().unwrap();
+                match event {
+                    Event::Key(key) => match key.code {
+                        KeyCode::Char(c) => input.push(c),
+                        KeyCode::Backspace => {
+                            input.pop();
+                        }
+                        KeyCode::Enter => app.import_profile(),
+                        KeyCode::Esc => app.input = None,
+                        _ => {}
+                    },
+                    Event::Paste(text) => input.push_str(text.trim()),
+                    _ => {}
+                }
+                terminal.draw(|f| {
+                    ui(
+                        f,
+                        &app,
+                        &message,
+                        &program_signal,
+                        &crafter_signal,
+                        &mut areas,
+                    )
+                })?;
+            } else if let Event::Mouse(mouse) = event {
+                let (column, row) = (mouse.column, mouse.row);
+                if app.quit.is_some() {
+                    // the quit confirmation is answered with keys, not by clicking past it
+                } else if app.help {
+                    // a click closes the help overlay like any key
+                    if let MouseEventKind::Down(_) = mouse.kind {
+                        app.help = false;
+                    }
+                } else {
+                    match mouse.kind {
+                        MouseEventKind::Down(MouseButton::Left) => {
+                            if let Some(index) =
+                                areas.tabs.iter().position(|tab| ui::hit(*tab, column, row))
+                            {
+                                app.index = index;
+                            } else if ui::hit(areas.start_pause, column, row) {
+                                start_pause(&mut app, &program_signal, &crafter_signal);
+                            } else if ui::hit(areas.stop, column, row) {
+                                stop(&program_signal, &crafter_signal);
+                            }
+                        }
+                        MouseEventKind::ScrollUp => {
+                            if ui::hit(areas.log, column, row) {
+                                app.scroll_log_up();
+                            } else if ui::hit(areas.config, column, row) {
+                                app.scroll_config_up(1);
+                            }
+                        }
+                        MouseEventKind::ScrollDown => {
+                            if ui::hit(areas.log, column, row) {
+                                app.scroll_log_down();
+                            } else if ui::hit(areas.config, column, row) {
+                                app.scroll_config_down(1);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                terminal.draw(|f| {
+                    ui(
+                        f,
+                        &app,
+                        &message,
+                        &program_signal,
+                        &crafter_signal,
+                        &mut areas,
+                    )
+                })?;
+            } else if let Event::Key(key) = event {
+                let keys = app.keys.clone();
+                let code = key.code;
+
+                if let Some(quit) = app.quit {
+                    // Quit Confirmation
+                    match (quit, code) {
+                        (Quit::Confirm, KeyCode::Char('1')) => {
+                            stop(&program_signal, &crafter_signal);
+                            hooks.state(&app.profile, "stopped");
+                            return Ok(());
+                        }
+                        (Quit::Confirm, KeyCode::Char('2')) => {
+                            crafter_signal.store(false, Ordering::Relaxed);
+                            app.quit = Some(Quit::AfterCraft);
+                        }
+                        (Quit::Confirm, KeyCode::Char('3')) => {
+                            close_signal.store(true, Ordering::Relaxed);
+                            crafter_signal.store(false, Ordering::Relaxed);
+                            app.quit = Some(Quit::AfterClose);
+                        }
+                        (_, KeyCode::Esc) => {
+                            close_signal.store(false, Ordering::Relaxed);
+                            app.quit = None;
+                        }
+                        _ => {}
+                    }
+                } else if app.help {
+                    // any key closes the help overlay
+                    app.help = false;
+                } else if utils::is_key(code, &keys.quit) {
+                    if !program_signal.load(Ordering::Relaxed) {
+                        stop(&program_signal, &crafter_signal);
+                        hooks.state(&app.profile, "stopped");
+                        return Ok(());
+                    }
+                    app.quit = Some(Quit::Confirm);
+                } else if utils::is_key(code, &keys.help) {
+                    app.help = true;
+                } else if utils::is_key(code, &keys.compact) {
+                    app.compact = !app.compact;
+                } else if utils::is_key(code, &keys.next_tab) {
                     app.next();
-                } else if key.code == KeyCode::Left {
+                } else if utils::is_key(code, &keys.previous_tab) {
                     app.previous();
-                } else if key.code == start_pause_hotkey {
-                    program_signal.store(true, Ordering::Relaxed);
-
-                    if crafter_signal.load(Ordering::Relaxed) {
-                        crafter_signal.store(false, Ordering::Relaxed);
-                    } else {
-                        crafter_signal.store(true, Ordering::Relaxed);
+                } else if code == start_pause_hotkey {
+                    start_pause(&mut app, &program_signal, &crafter_signal);
+                } else if code == stop_hotkey {
+                    stop(&program_signal, &crafter_signal);
+                } else if app.index == 0 {
+                    // Home Tab
+                    if utils::is_key(code, &keys.up) {
+                        app.scroll_log_up();
+                    } else if utils::is_key(code, &keys.down) {
+                        app.scroll_log_down();
+                    } else if utils::is_key(code, &keys.latest) {
+                        app.log_scroll = 0;
+                    } else if utils::is_key(code, &keys.filter) {
+                        app.next_log_filter();
+                    }
+                } else if app.index == 1 {
+                    // Profiles Tab
+                    if utils::is_key(code, &keys.up) {
+                        app.previous_profile();
+                    } else if utils::is_key(code, &keys.down) {
+                        app.next_profile();
+                    } else if utils::is_key(code, &keys.select) {
+                        if program_signal.load(Ordering::Relaxed) {
+                            app.profile_status =
+                                Some(String::from("Stop crafting before switching profiles"));
+                        } else {
+                            app.use_profile();
+                            if let Err(e) = send_profile(&updates, &app.profile) {
+                                app.error = Some(format!("Unable to update the crafter: {}", e));
+                            }
+                        }
+                    } else if utils::is_key(code, &keys.export) {
+                        app.export_profile();
+                    } else if utils::is_key(code, &keys.export_file) {
+                        app.export_profile_file();
+                    } else if utils::is_key(code, &keys.import) {
+                        app.input = Some(String::new());
+                    }
+                } else if app.index == 2 {
+                    // Config Tab
+                    if utils::is_key(code, &keys.up) {
+                        app.previous_backup();
+                    } else if utils::is_key(code, &keys.down) {
+                        app.next_backup();
+                    } else if utils::is_key(code, &keys.restore) {
+                        app.restore_backup();
+                    } else if utils::is_key(code, &keys.page_up) {
+                        app.scroll_config_up(CONFIG_PAGE);
+                    } else if utils::is_key(code, &keys.page_down) {
+                        app.scroll_config_down(CONFIG_PAGE);
                     }
-                } else if key.code == stop_hotkey {
-                    program_signal.store(false, Ordering::Relaxed);
-                    crafter_signal.store(false, Ordering::Relaxed);
                 }
+
+                terminal.draw(|f| {
+                    ui(
+                        f,
+                        &app,
+                        &message,
+                        &program_signal,
+                        &crafter_signal,
+                        &mut areas,
+                    )
+                })?;
             }
         }
 
         if last_tick.elapsed() >= TICK_RATE {
-            app.update();
-
-            match receiver.recv_timeout(Duration::from_millis(100)) {
-                Ok(val) => {
-                    let (crafted, food, potion, msg) = val;
-                    app.current_amount = crafted;
-                    app.food_count = food;
-                    app.potion_count = potion;
-                    message = msg;
+            if changes.try_iter().count() > 0 {
+                app.update();
+                let sent = send_profile(&updates, &app.profile);
+
+                notifier.settings = app.notifications.clone();
+                if let Some(error) = &app.error {
+                    notifier.notify(Notice::Error, error);
+                    hooks.error(&app.profile, error);
                 }
-                Err(mpsc::RecvTimeoutError::Timeout) => {}
-                Err(e) => {
-                    eprintln!("Error: {:?}", e);
+                // the crafter stopping has already been reported
+                if let Err(e) = sent {
+                    app.error = Some(format!("Unable to update the crafter: {}", e));
                 }
             }
 
-            terminal.draw(|f| ui(f, &app, &message, &program_signal, &crafter_signal))?;
+            let mut waiting = false;
+            loop {
+                match receiver.try_recv() {
+                    Ok(msg) => {
+                        waiting |= msg.kind == Kind::Status;
+                        message = msg.text.clone();
+                        notifier.message(&msg, &app.profile);
+                        hooks.message(&msg, &app.profile);
+                        events.publish(&msg, control::state(&program_signal, &crafter_signal));
+                        app.log_message(msg);
+                    }
+                    Err(mpsc::TryRecvError::Empty) => break,
+                    Err(e) => {
+                        if !crashed {
+                            crashed = true;
+                            let error = format!("Crafter stopped unexpectedly: {:?}", e);
+                            notifier.notify(Notice::Error, &error);
+                            hooks.error(&app.profile, &error);
+                            app.error = Some(error);
+                        }
+                        break;
+                    }
+                }
+            }
+
+            let mut handler = Requests {
+                app: &mut app,
+                updates: &updates,
+            };
+            for request in requests.try_iter() {
+                control::answer(request, &mut handler, &program_signal, &crafter_signal);
+            }
+
+            // run hooks after requests, so a stop is seen this tick
+            hooks.state(
+                &app.profile,
+                control::state(&program_signal, &crafter_signal),
+            );
+            for msg in hook_output.try_iter() {
+                events.publish(&msg, control::state(&program_signal, &crafter_signal));
+                app.log_entry(msg);
+            }
+
+            if let Some(exporter) = &mut exporter {
+                let state = control::state(&program_signal, &crafter_signal);
+                exporter.update(&app.status(state));
+            }
+
+            // quit once the crafter is done
+            let done = match app.quit {
+                Some(Quit::AfterCraft) => waiting || !program_signal.load(Ordering::Relaxed),
+                Some(Quit::AfterClose) => !close_signal.load(Ordering::Relaxed),
+                _ => false,
+            };
+            if done {
+                stop(&program_signal, &crafter_signal);
+                hooks.state(&app.profile, "stopped");
+                return Ok(());
+            }
+
+            terminal.draw(|f| {
+                ui(
+                    f,
+                    &app,
+                    &message,
+                    &program_signal,
+                    &crafter_signal,
+                    &mut areas,
+                )
+            })?;
 
             last_tick = Instant::now();
         }
     }
 }
+
+/// start_pause starts the crafter, or pauses it after the current craft
+///
+/// The crafter is only started or resumed with a valid profile.
+fn start_pause(app: &mut App, program_signal: &AtomicBool, crafter_signal: &AtomicBool) {
+    if !crafter_signal.load(Ordering::Relaxed) && app.check_profile().is_err() {
+        return;
+    }
+
+    program_signal.store(true, Ordering::Relaxed);
+
+    if crafter_signal.load(Ordering::Relaxed) {
+        crafter_signal.store(false, Ordering::Relaxed);
+    } else {
+        crafter_signal.store(true, Ordering::Relaxed);
+    }
+}
+
+/// stop stops the crafter after the current craft
+fn stop(program_signal: &AtomicBool, crafter_signal: &AtomicBool) {
+    program_signal.store(false, Ordering::Relaxed);
+    crafter_signal.store(false, Ordering::Relaxed);
+}
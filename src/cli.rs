@@ -0,0 +1,435 @@
+use crate::app::TICK_RATE;
+use crate::config::{self, Config, Conflict, Notice, Profile, Selection, Store};
+use crate::control::{self, Events, Handler};
+use crate::crafter::{self, Kind, Message};
+use crate::hooks::Runner;
+use crate::notification::{self, Notifier};
+use crate::progress::{Progress, Status};
+use crate::share;
+use crate::status_file::Exporter;
+
+use clap::{Parser, Subcommand};
+
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+};
+
+/// Automatically activates multiple crafting macros while refreshing food and potion buffs
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Path to the config directory, or to a single JSON config file
+    /// [default: $XDG_CONFIG_HOME/xivcrafter]
+    #[arg(short, long, global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Name or id of the profile to use instead of the last used one
+    #[arg(short, long, global = true)]
+    pub profile: Option<String>,
+
+    /// Amount to craft instead of the profile's amount
+    #[arg(short, long, global = true)]
+    pub amount: Option<i32>,
+
+    /// Start the TUI in the compact single-column view
+    #[arg(long)]
+    pub compact: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Craft without the TUI, printing progress to stdout
+    Run,
+    /// Check the config for errors
+    Validate,
+    /// List every profile in the config
+    ListProfiles,
+    /// Create a config with an empty profile
+    Init {
+        /// Overwrite the config file if it already exists
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// Print a profile as a share string, or write it to a standalone file
+    Export {
+        /// File to write the profile to instead of printing a share string
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Add a profile from a share string or a profile file
+    Import {
+        /// Share string, path to a profile file or a JSON profile
+        input: String,
+
+        /// What to do when a profile with the same name exists
+        #[arg(long, value_enum, default_value_t = Conflict::Rename)]
+        on_conflict: Conflict,
+    },
+    /// Send a test notification on every configured channel
+    TestNotify,
+    /// Control a running XIVCrafter, e.g. from a desktop-wide shortcut
+    Ctl {
+        #[command(subcommand)]
+        command: Ctl,
+    },
+}
+
+#[derive(Subcommand, Clone, Copy)]
+pub enum Ctl {
+    /// Start or resume crafting
+    Start,
+    /// Pause after the current craft
+    Pause,
+    /// Stop after the current craft
+    Stop,
+    /// Print the crafter's status as JSON
+    Status,
+    /// Change the amount to craft
+    SetAmount { amount: i32 },
+}
+
+impl From<Ctl> for control::Command {
+    fn from(ctl: Ctl) -> control::Command {
+        match ctl {
+            Ctl::Start => control::Command::Start,
+            Ctl::Pause => control::Command::Pause,
+            Ctl::Stop => control::Command::Stop,
+            Ctl::Status => control::Command::Status,
+            Ctl::SetAmount { amount } => control::Command::SetAmount(amount),
+        }
+    }
+}
+
+impl Cli {
+    /// selection returns the profile selection given on the command line
+    pub fn selection(&self) -> Selection {
+        Selection {
+            profile: self.profile.clone(),
+            amount: self.amount,
+        }
+    }
+}
+
+/// run crafts the selected profile without the TUI
+pub fn run(store: &Store, selection: Selection) -> Result<(), Box<dyn Error>> {
+    let configs = store.load()?;
+    let mut profile = selection.select(&configs).ok_or("Unable to find profile")?;
+
+    let errors = config::validate(&profile);
+    if !errors.is_empty() {
+        for (field, message) in errors {
+            eprintln!("{}: {}", field, message);
+        }
+        return Err(format!("Profile \"{}\" is not valid", profile.name).into());
+    }
+
+    println!("Crafting {} x{}", profile.name, profile.amount);
+
+    let settings = store.load_settings()?;
+    let mut notifier = Notifier::new(settings.notifications.clone());
+    let (mut hooks, hook_output) = Runner::new();
+
+    let program_signal = Arc::new(AtomicBool::new(true));
+    let crafter_signal = Arc::new(AtomicBool::new(true));
+
+    let (updates, config_receiver) = mpsc::channel();
+    let receiver = crafter::craft(
+        profile.clone(),
+        config_receiver,
+        program_signal.clone(),
+        crafter_signal.clone(),
+        Arc::new(AtomicBool::new(false)),
+    );
+
+    // accept commands from the control socket and the HTTP API
+    let (requests_tx, requests) = mpsc::channel();
+    let events = Events::default();
+    let _server = match control::listen(requests_tx.clone()) {
+        Ok(server) => Some(server),
+        Err(e) => {
+            eprintln!("warning: unable to open control socket: {}", e);
+            None
+        }
+    };
+    control::listen_http(&settings, requests_tx, &events)
+        .map_err(|e| format!("Unable to start HTTP API: {}", e))?;
+
+    let mut progress = Progress::default();
+    let mut exporter = settings.status_file.clone().map(Exporter::new);
+    let mut last_message = String::new();
+    // error that stopped the crafter, which fails the run
+    let mut failure = None;
+    loop {
+        hooks.state(&profile, control::state(&program_signal, &crafter_signal));
+        for message in hook_output.try_iter() {
+            println!("[hook] {}", message.text);
+        }
+
+        let mut handler = Run {
+            profile: &mut profile,
+            progress: &progress,
+            updates: &updates,
+        };
+        for request in requests.try_iter() {
+            control::answer(request, &mut handler, &program_signal, &crafter_signal);
+        }
+
+        if let Some(exporter) = &mut exporter {
+            let state = control::state(&program_signal, &crafter_signal);
+            exporter.update(&progress.status(&profile, state));
+        }
+
+        match receiver.recv_timeout(TICK_RATE) {
+            Ok(message) => {
+                progress.record(&message);
+                events.publish(&message, control::state(&program_signal, &crafter_signal));
+
+                if message.text != last_message {
+                    println!(
+                        "[{}/{}] {} (food: {}, potion: {})",
+                        message.crafted, profile.amount, message.text, message.food, message.potion
+                    );
+                    last_message = message.text.clone();
+                }
+                notifier.message(&message, &profile);
+                hooks.message(&message, &profile);
+
+                if !program_signal.load(Ordering::Relaxed) && message.crafted >= profile.amount {
+                    println!("Finished crafting {} x{}", profile.name, message.crafted);
+                    finish_hooks(hooks, &hook_output, &profile);
+                    return Ok(());
+                }
+
+                if message.kind == Kind::Error {
+                    failure = Some(message.text.clone());
+                }
+
+                // stopped from the control socket or by an error before the run finished
+                if !program_signal.load(Ordering::Relaxed) && message.kind == Kind::Status {
+                    println!("Stopped crafting {} at {}", profile.name, message.crafted);
+                    finish_hooks(hooks, &hook_output, &profile);
+                    return match failure {
+                        Some(error) => Err(error.into()),
+                        None => Ok(()),
+                    };
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                notifier.notify(Notice::Error, "Crafter stopped unexpectedly");
+                hooks.error(&profile, "Crafter stopped unexpectedly");
+                finish_hooks(hooks, &hook_output, &profile);
+                return Err("Crafter stopped unexpectedly".into());
+            }
+        }
+    }
+}
+
+/// finish_hooks runs the run finish hook and prints the output of every hook still running
+fn finish_hooks(mut hooks: Runner, output: &mpsc::Receiver<Message>, profile: &Config) {
+    hooks.state(profile, "stopped");
+    drop(hooks);
+    for message in output.try_iter() {
+        println!("[hook] {}", message.text);
+    }
+}
+
+/// Run answers control requests from a headless run's state
+struct Run<'a> {
+    profile: &'a mut Config,
+    progress: &'a Progress,
+    updates: &'a mpsc::Sender<Config>,
+}
+
+impl Handler for Run<'_> {
+    fn check(&mut self) -> Result<(), String> {
+        // the profile was validated before the run and only its amount can change
+        Ok(())
+    }
+
+    fn set_amount(&mut self, amount: i32) -> Result<(), String> {
+        self.profile.amount = amount;
+        self.updates
+            .send(self.profile.clone())
+            .map_err(|_| String::from("the crafter has stopped"))
+    }
+
+    fn status(&self, state: &'static str) -> Status {
+        self.progress.status(self.profile, state)
+    }
+}
+
+/// validate prints every problem found in the config
+pub fn validate(store: &Store) -> Result<(), Box<dyn Error>> {
+    let configs = store.load()?;
+
+    let mut errors = config::validate_all(&configs);
+    let keys = store.load_settings()?.keys;
+    for (name, message) in keys.validate() {
+        errors.push(format!("keys.{}: {}", name, message));
+    }
+    for config in &configs {
+        for (field, message) in keys.clashes(config) {
+            errors.push(format!(
+                "profile {} ({}): {} {}",
+                config.id, config.name, field, message
+            ));
+        }
+    }
+    if errors.is_empty() {
+        println!("{} is valid", store.path().display());
+        return Ok(());
+    }
+
+    for error in &errors {
+        eprintln!("{}", error);
+    }
+    Err(format!(
+        "Found {} error(s) in {}",
+        errors.len(),
+        store.path().display()
+    )
+    .into())
+}
+
+/// list_profiles prints every profile in the config
+pub fn list_profiles(store: &Store) -> Result<(), Box<dyn Error>> {
+    let configs = store.load()?;
+
+    for config in configs {
+        let marker = if config.last_used { "*" } else { " " };
+        println!(
+            "{} {:>3}  {:<30} x{}",
+            marker, config.id, config.name, config.amount
+        );
+    }
+
+    Ok(())
+}
+
+/// export prints the selected profile as a share string or writes it to a file
+pub fn export(
+    store: &Store,
+    selection: Selection,
+    output: Option<&Path>,
+) -> Result<(), Box<dyn Error>> {
+    let configs = store.load()?;
+    let profile = selection.select(&configs).ok_or("Unable to find profile")?;
+    let shared = Profile::share(&profile);
+
+    match output {
+        Some(path) => {
+            share::write(&shared, path)?;
+            eprintln!("Exported {} to {}", profile.name, path.display());
+        }
+        None => println!("{}", share::encode(&shared)?),
+    }
+
+    Ok(())
+}
+
+/// import adds a profile from a share string or file to the store
+pub fn import(store: &Store, input: &str, conflict: Conflict) -> Result<(), Box<dyn Error>> {
+    let profile = share::read(input)?;
+    let missing = profile.missing_hotkeys();
+    let (config, dropped) = store.import(profile, conflict)?;
+    println!("Imported {} as profile {}", config.name, config.id);
+    if !dropped.is_empty() {
+        eprintln!(
+            "warning: left out {} since they run commands on this machine",
+            dropped.join(" and ")
+        );
+    }
+
+    // hotkeys the sender took from their settings are not in the share
+    if !missing.is_empty() {
+        let keys: Vec<String> = [
+            ("start_pause", &config.start_pause),
+            ("stop", &config.stop),
+            ("confirm", &config.confirm),
+            ("cancel", &config.cancel),
+        ]
+        .into_iter()
+        .filter(|(field, _)| missing.contains(field))
+        .map(|(field, key)| format!("{} \"{}\"", field, key))
+        .collect();
+        eprintln!(
+            "warning: the profile does not include every hotkey, using this machine's {}",
+            keys.join(", ")
+        );
+    }
+
+    for (field, message) in config::validate(&config) {
+        eprintln!("warning: {} {}", field, message);
+    }
+
+    Ok(())
+}
+
+/// test_notify sends a test notification on every channel set in the settings
+pub fn test_notify(store: &Store) -> Result<(), Box<dyn Error>> {
+    let settings = store.load_settings()?.notifications;
+    if !settings.bell && !settings.desktop && settings.webhook.is_none() {
+        return Err("No notification channels are enabled".into());
+    }
+
+    if settings.bell {
+        notification::bell();
+        println!("Rang the terminal bell");
+    }
+
+    let errors = notification::deliver(&settings, "Test", "Notifications are working");
+    if !errors.is_empty() {
+        for error in &errors {
+            eprintln!("{}", error);
+        }
+        return Err(format!("Unable to send {} notification(s)", errors.len()).into());
+    }
+
+    if settings.desktop {
+        println!("Sent a desktop notification");
+    }
+    if let Some(url) = &settings.webhook {
+        println!("Posted to {}", url);
+    }
+
+    Ok(())
+}
+
+/// ctl sends a command to a running XIVCrafter and prints its answer
+pub fn ctl(command: Ctl) -> Result<(), Box<dyn Error>> {
+    let answer = control::send(command.into())
+        .map_err(|e| format!("Unable to reach a running XIVCrafter: {}", e))?;
+
+    match answer.strip_prefix("error: ") {
+        Some(error) => Err(error.into()),
+        None => {
+            println!("{}", answer);
+            Ok(())
+        }
+    }
+}
+
+/// init creates the config file or directory
+pub fn init(store: &Store, force: bool) -> Result<(), Box<dyn Error>> {
+    if store.exists() && !force {
+        return Err(format!(
+            "{} already exists, use --force to overwrite",
+            store.path().display()
+        )
+        .into());
+    }
+
+    store.init()?;
+    println!("Created {}", store.path().display());
+
+    Ok(())
+}
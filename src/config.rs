@@ -0,0 +1,1768 @@
+use crate::persist::{Backup, Persist, DEFAULT_BACKUPS};
+use crate::script::Script;
+use crate::theme::ThemeName;
+use crate::utils;
+
+use clap::ValueEnum;
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use serde::{Deserialize, Serialize};
+
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::mpsc,
+};
+
+/// global settings file in the config directory
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// directory of profile files in the config directory
+const PROFILES_DIR: &str = "profiles";
+
+/// directory of backups in the config directory
+const BACKUPS_DIR: &str = "backups";
+
+/// Config is a profile combined with the global settings
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Config {
+    pub id: i32,
+    pub last_used: bool,
+    pub name: String,
+    pub amount: i32,
+    pub food: String,
+    pub food_duration: i64,
+    pub potion: String,
+    pub macro1: String,
+    pub macro1_duration: u64,
+    pub macro2: String,
+    pub macro2_duration: u64,
+    pub macro3: String,
+    pub macro3_duration: u64,
+    pub start_pause: String,
+    pub stop: String,
+    pub confirm: String,
+    pub cancel: String,
+    /// food and potions on hand at the start of a run, 0 when not tracked
+    #[serde(default)]
+    pub food_stock: i32,
+    #[serde(default)]
+    pub potion_stock: i32,
+    /// commands run on crafter events
+    #[serde(default, skip_serializing_if = "Hooks::is_empty")]
+    pub hooks: Hooks,
+    /// Rhai script run for each craft instead of the built-in order, empty when not used
+    ///
+    /// A relative path is relative to the config directory, see script_path.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub script: String,
+    /// steps run for each craft instead of the built-in order, empty when not used
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<Action>,
+    /// keys pressed to open and close the synthesis window, empty for the defaults
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub open_synthesis: Vec<KeyPress>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub close_synthesis: Vec<KeyPress>,
+    /// whether to craft with macros or Quick Synthesis
+    #[serde(default, skip_serializing_if = "Mode::is_default")]
+    pub mode: Mode,
+    #[serde(default, skip_serializing_if = "QuickSynthesis::is_default")]
+    pub quick_synthesis: QuickSynthesis,
+
+    /// where each value came from, empty for single-file configs
+    #[serde(skip)]
+    pub sources: Sources,
+    /// directory of the store the config was loaded from
+    #[serde(skip)]
+    pub dir: PathBuf,
+}
+
+impl Config {
+    /// source returns the layer a field came from
+    pub fn source(&self, field: &str) -> Source {
+        self.sources.get(field).cloned().unwrap_or(Source::Profile)
+    }
+
+    /// script_path returns the script's path, resolving a relative one against the
+    /// directory of the store, so it does not depend on where XIVCrafter is started
+    pub fn script_path(&self) -> PathBuf {
+        self.dir.join(&self.script)
+    }
+
+    /// macros returns the number, key and duration of each macro that is set, in order
+    pub fn macros(&self) -> Vec<(usize, &str, u64)> {
+        [
+            (1, &self.macro1, self.macro1_duration),
+            (2, &self.macro2, self.macro2_duration),
+            (3, &self.macro3, self.macro3_duration),
+        ]
+        .into_iter()
+        .filter(|(_, key, _)| !key.is_empty())
+        .map(|(number, key, duration)| (number, key.as_str(), duration))
+        .collect()
+    }
+
+    /// open_synthesis returns the keys pressed to open the synthesis window, by default
+    /// confirm three times
+    pub fn open_synthesis(&self) -> Vec<KeyPress> {
+        if !self.open_synthesis.is_empty() {
+            return self.open_synthesis.clone();
+        }
+
+        ["confirm", "confirm", "confirm"]
+            .into_iter()
+            .map(KeyPress::new)
+            .collect()
+    }
+
+    /// close_synthesis returns the keys pressed to close the synthesis window, by default
+    /// confirm, cancel and confirm
+    pub fn close_synthesis(&self) -> Vec<KeyPress> {
+        if !self.close_synthesis.is_empty() {
+            return self.close_synthesis.clone();
+        }
+
+        ["confirm", "cancel", "confirm"]
+            .into_iter()
+            .map(KeyPress::new)
+            .collect()
+    }
+
+    /// key resolves "confirm" and "cancel" to the profile's keys, leaving other keys as is
+    pub fn key<'a>(&'a self, key: &'a str) -> &'a str {
+        match key {
+            "confirm" => &self.confirm,
+            "cancel" => &self.cancel,
+            _ => key,
+        }
+    }
+
+    /// steps returns the steps run for each craft, the profile's own or the built-in
+    /// order of starting the craft, checking food and potion, then each macro
+    pub fn steps(&self) -> Vec<Action> {
+        if !self.steps.is_empty() {
+            return self.steps.clone();
+        }
+
+        let mut steps = vec![Action::StartCraft];
+        if !self.food.is_empty() {
+            steps.push(Action::EnsureBuff { name: Buff::Food });
+        }
+        if !self.potion.is_empty() {
+            steps.push(Action::EnsureBuff { name: Buff::Potion });
+        }
+        for (_, key, duration) in self.macros() {
+            steps.push(Action::Macro {
+                key: key.to_string(),
+                duration,
+            });
+        }
+        steps
+    }
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            id: 0,
+            last_used: true,
+            name: String::from(""),
+            amount: 0,
+            food: String::from(""),
+            food_duration: 0,
+            potion: String::from(""),
+            macro1: String::from(""),
+            macro1_duration: 0,
+            macro2: String::from(""),
+            macro2_duration: 0,
+            macro3: String::from(""),
+            macro3_duration: 0,
+            start_pause: String::from(""),
+            stop: String::from(""),
+            confirm: String::from(""),
+            cancel: String::from(""),
+            food_stock: 0,
+            potion_stock: 0,
+            hooks: Hooks::default(),
+            script: String::from(""),
+            steps: Vec::new(),
+            open_synthesis: Vec::new(),
+            close_synthesis: Vec::new(),
+            mode: Mode::default(),
+            quick_synthesis: QuickSynthesis::default(),
+            sources: Sources::new(),
+            dir: PathBuf::new(),
+        }
+    }
+}
+
+/// Selection picks which profile to use and overrides parts of it
+#[derive(Clone, Default)]
+pub struct Selection {
+    /// profile name or id, falls back to the last used profile
+    pub profile: Option<String>,
+    /// amount to craft instead of the profile's amount
+    pub amount: Option<i32>,
+}
+
+impl Selection {
+    /// select returns the chosen profile with the overrides applied
+    pub fn select(&self, configs: &[Config]) -> Option<Config> {
+        let config = match &self.profile {
+            Some(profile) => find(configs, profile),
+            None => configs.iter().find(|c| c.last_used).or(configs.first()),
+        };
+
+        config.map(|config| {
+            let mut config = config.clone();
+            if let Some(amount) = self.amount {
+                config.amount = amount;
+                config.sources.insert("amount", Source::CommandLine);
+            }
+            config
+        })
+    }
+}
+
+/// find looks up a profile by id, then by name
+pub fn find<'a>(configs: &'a [Config], profile: &str) -> Option<&'a Config> {
+    if let Ok(id) = profile.parse::<i32>() {
+        if let Some(config) = configs.iter().find(|c| c.id == id) {
+            return Some(config);
+        }
+    }
+
+    configs.iter().find(|c| c.name == profile).or_else(|| {
+        configs
+            .iter()
+            .find(|c| c.name.eq_ignore_ascii_case(profile))
+    })
+}
+
+/// Source is the layer a resolved value came from
+#[derive(Clone, PartialEq)]
+pub enum Source {
+    /// built-in default, nothing set the value
+    Default,
+    /// settings.json
+    Global,
+    /// job template in settings.json
+    Job(String),
+    /// profile file
+    Profile,
+    /// command-line override
+    CommandLine,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::Global => write!(f, "global"),
+            Source::Job(job) => write!(f, "job {}", job),
+            Source::Profile => write!(f, "profile"),
+            Source::CommandLine => write!(f, "command line"),
+        }
+    }
+}
+
+/// Sources maps each resolved field to the layer it came from
+pub type Sources = BTreeMap<&'static str, Source>;
+
+/// Layer is a set of settings where every value is optional
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Layer {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub food: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub food_duration: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub potion: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub macro1: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub macro1_duration: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub macro2: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub macro2_duration: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub macro3: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub macro3_duration: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_pause: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirm: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cancel: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub food_stock: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub potion_stock: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<Hooks>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub script: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub steps: Option<Vec<Action>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub open_synthesis: Option<Vec<KeyPress>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub close_synthesis: Option<Vec<KeyPress>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<Mode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quick_synthesis: Option<QuickSynthesis>,
+}
+
+/// Global settings shared by every profile
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Settings {
+    pub last_used: i32,
+
+    /// backups kept of each config file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backups: Option<usize>,
+
+    /// start the TUI in the compact single-column view
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compact: Option<bool>,
+
+    /// colour theme of the TUI
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theme: Option<ThemeName>,
+
+    /// defaults for every profile
+    #[serde(flatten)]
+    pub defaults: Layer,
+
+    /// templates applied to profiles of a job, between the defaults and the profile
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub jobs: BTreeMap<String, Layer>,
+
+    /// TUI keybindings
+    #[serde(skip_serializing_if = "Keys::is_default")]
+    pub keys: Keys,
+
+    /// where and when to send notifications
+    #[serde(skip_serializing_if = "Notifications::is_default")]
+    pub notifications: Notifications,
+
+    /// HTTP API and dashboard, off unless set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http: Option<Http>,
+
+    /// files the crafter's status is written to, off unless set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status_file: Option<StatusFile>,
+}
+
+impl Settings {
+    /// from_config uses a profile's hotkeys as the shared hotkeys
+    fn from_config(config: &Config) -> Settings {
+        Settings {
+            last_used: config.id,
+            backups: None,
+            compact: None,
+            theme: None,
+            defaults: Layer {
+                start_pause: Some(config.start_pause.clone()),
+                stop: Some(config.stop.clone()),
+                confirm: Some(config.confirm.clone()),
+                cancel: Some(config.cancel.clone()),
+                ..Layer::default()
+            },
+            jobs: BTreeMap::new(),
+            keys: Keys::default(),
+            notifications: Notifications::default(),
+            http: None,
+            status_file: None,
+        }
+    }
+}
+
+/// Keys are the keybindings used to navigate the TUI
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct Keys {
+    pub quit: String,
+    pub help: String,
+    pub compact: String,
+    pub next_tab: String,
+    pub previous_tab: String,
+    pub up: String,
+    pub down: String,
+    pub page_up: String,
+    pub page_down: String,
+    pub latest: String,
+    pub filter: String,
+    pub select: String,
+    pub export: String,
+    pub export_file: String,
+    pub import: String,
+    pub restore: String,
+}
+
+impl Default for Keys {
+    fn default() -> Keys {
+        Keys {
+            quit: String::from("q"),
+            help: String::from("?"),
+            compact: String::from("m"),
+            next_tab: String::from("right"),
+            previous_tab: String::from("left"),
+            up: String::from("up"),
+            down: String::from("down"),
+            page_up: String::from("pageup"),
+            page_down: String::from("pagedown"),
+            latest: String::from("end"),
+            filter: String::from("f"),
+            select: String::from("enter"),
+            export: String::from("e"),
+            export_file: String::from("f"),
+            import: String::from("i"),
+            restore: String::from("r"),
+        }
+    }
+}
+
+impl Keys {
+    fn is_default(&self) -> bool {
+        *self == Keys::default()
+    }
+
+    /// validate returns every keybinding that is not a known key as (name, message)
+    pub fn validate(&self) -> Vec<(&'static str, String)> {
+        [
+            ("quit", &self.quit),
+            ("help", &self.help),
+            ("compact", &self.compact),
+            ("next_tab", &self.next_tab),
+            ("previous_tab", &self.previous_tab),
+            ("up", &self.up),
+            ("down", &self.down),
+            ("page_up", &self.page_up),
+            ("page_down", &self.page_down),
+            ("latest", &self.latest),
+            ("filter", &self.filter),
+            ("select", &self.select),
+            ("export", &self.export),
+            ("export_file", &self.export_file),
+            ("import", &self.import),
+            ("restore", &self.restore),
+        ]
+        .into_iter()
+        .filter(|(_, key)| utils::get_crossterm_key_code(key).is_none())
+        .map(|(name, key)| (name, format!("\"{}\" is not a valid key", key)))
+        .collect()
+    }
+
+    /// clashes returns the profile's hotkeys that are also a key the TUI reads before
+    /// them, which would never fire, as (field, message)
+    pub fn clashes(&self, config: &Config) -> Vec<(&'static str, String)> {
+        let keys = [
+            ("quit", &self.quit),
+            ("help", &self.help),
+            ("compact", &self.compact),
+            ("next_tab", &self.next_tab),
+            ("previous_tab", &self.previous_tab),
+        ];
+
+        let mut clashes = Vec::new();
+        for (field, hotkey) in [("start_pause", &config.start_pause), ("stop", &config.stop)] {
+            let code = utils::get_crossterm_key_code(hotkey);
+            if code.is_none() {
+                continue;
+            }
+            for (name, key) in keys {
+                if utils::get_crossterm_key_code(key) == code {
+                    clashes.push((field, format!("\"{}\" is also the {} key", hotkey, name)));
+                }
+            }
+        }
+        clashes
+    }
+}
+
+/// Notifications choose where and when to send notifications
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct Notifications {
+    /// ring the terminal bell
+    pub bell: bool,
+    /// show a desktop notification
+    pub desktop: bool,
+    /// URL to post Discord-style JSON to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<String>,
+    /// events to notify about
+    pub events: Vec<Notice>,
+    /// food or potions left on hand at which to warn about low stock
+    pub low_stock: i32,
+}
+
+impl Default for Notifications {
+    fn default() -> Notifications {
+        Notifications {
+            bell: false,
+            desktop: false,
+            webhook: None,
+            events: vec![Notice::Complete, Notice::Error, Notice::LowStock],
+            low_stock: 5,
+        }
+    }
+}
+
+impl Notifications {
+    fn is_default(&self) -> bool {
+        *self == Notifications::default()
+    }
+}
+
+/// Notice is an event that can be notified about
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Notice {
+    Complete,
+    Error,
+    LowStock,
+}
+
+impl fmt::Display for Notice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Notice::Complete => write!(f, "Run complete"),
+            Notice::Error => write!(f, "Error"),
+            Notice::LowStock => write!(f, "Low stock"),
+        }
+    }
+}
+
+/// Http sets up the HTTP API and dashboard
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct Http {
+    /// address and port to listen on
+    pub address: String,
+    /// token every request must send, as a bearer token or ?token=
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+impl Default for Http {
+    fn default() -> Http {
+        Http {
+            address: String::from("127.0.0.1:8460"),
+            token: None,
+        }
+    }
+}
+
+/// StatusFile sets where the crafter's status is written for status bars
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct StatusFile {
+    /// file to write the status to as JSON
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub json: Option<PathBuf>,
+    /// file to write the status to as one line of text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<PathBuf>,
+    /// least seconds between writes
+    pub interval: u64,
+}
+
+impl Default for StatusFile {
+    fn default() -> StatusFile {
+        StatusFile {
+            json: None,
+            text: None,
+            interval: 1,
+        }
+    }
+}
+
+/// Hooks are shell commands run on crafter events
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct Hooks {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_start: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_finish: Option<String>,
+    /// run after every n crafts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub every: Option<Every>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub food: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub potion: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// seconds a hook may run before it is killed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+}
+
+impl Hooks {
+    pub fn is_empty(&self) -> bool {
+        *self == Hooks::default()
+    }
+}
+
+/// Every runs a command after every n crafts
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct Every {
+    pub n: i32,
+    pub command: String,
+}
+
+/// longest a wait step may be, in seconds
+pub const MAX_WAIT: f64 = 3600.0;
+
+/// most times a repeat step may run its steps
+pub const MAX_REPEAT: i32 = 100;
+
+/// Action is a step of a profile's craft loop
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// open the synthesis window with the profile's open_synthesis keys
+    StartCraft,
+    /// close the synthesis window with the profile's close_synthesis keys
+    StopCraft,
+    /// press a key
+    Key(String),
+    /// wait a number of seconds
+    Wait(f64),
+    /// activate a macro and wait for it to finish
+    Macro { key: String, duration: u64 },
+    /// renew a buff that has run out
+    EnsureBuff { name: Buff },
+    /// run steps on every nth craft
+    Every { n: i32, steps: Vec<Action> },
+    /// run steps n times
+    Repeat { n: i32, steps: Vec<Action> },
+}
+
+/// Mode is how the crafter makes each item
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    /// synthesize one item at a time with macros
+    #[default]
+    Macro,
+    /// make items in batches with the game's Quick Synthesis
+    QuickSynthesis,
+}
+
+impl Mode {
+    fn is_default(&self) -> bool {
+        *self == Mode::default()
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mode::Macro => write!(f, "Macro"),
+            Mode::QuickSynthesis => write!(f, "Quick Synthesis"),
+        }
+    }
+}
+
+/// most items the game makes in one Quick Synthesis
+pub const QUICK_SYNTHESIS_CAP: i32 = 99;
+
+/// longest an item may take with Quick Synthesis, in seconds
+pub const MAX_ITEM_DURATION: f64 = 600.0;
+
+/// QuickSynthesis is how to run the game's Quick Synthesis dialog
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct QuickSynthesis {
+    /// keys pressed to open the dialog, before the amount is typed
+    pub open: Vec<KeyPress>,
+    /// keys pressed after the amount to start the batch
+    pub start: Vec<KeyPress>,
+    /// keys pressed to close the dialog once the batch is done
+    pub close: Vec<KeyPress>,
+    /// most items per batch
+    pub batch: i32,
+    /// seconds the game takes per item
+    pub item_duration: f64,
+}
+
+impl Default for QuickSynthesis {
+    fn default() -> QuickSynthesis {
+        QuickSynthesis {
+            open: Vec::new(),
+            start: vec![KeyPress::new("confirm")],
+            close: vec![KeyPress::new("confirm")],
+            batch: QUICK_SYNTHESIS_CAP,
+            item_duration: 3.0,
+        }
+    }
+}
+
+impl QuickSynthesis {
+    fn is_default(&self) -> bool {
+        *self == QuickSynthesis::default()
+    }
+}
+
+/// KeyPress is a key pressed while opening or closing the synthesis window
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct KeyPress {
+    /// key, or "confirm" or "cancel" for the profile's keys
+    pub key: String,
+    /// milliseconds to wait after the key, 500 when not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay: Option<u64>,
+}
+
+impl KeyPress {
+    fn new(key: &str) -> KeyPress {
+        KeyPress {
+            key: key.to_string(),
+            delay: None,
+        }
+    }
+}
+
+/// Buff is a consumable kept up by ensure_buff
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Buff {
+    Food,
+    Potion,
+}
+
+impl fmt::Display for Buff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Buff::Food => write!(f, "food"),
+            Buff::Potion => write!(f, "potion"),
+        }
+    }
+}
+
+/// validate_quick_synthesis checks the Quick Synthesis settings
+fn validate_quick_synthesis(config: &Config, errors: &mut Vec<(&'static str, String)>) {
+    let quick = &config.quick_synthesis;
+    let mut error = |message: String| errors.push(("quick_synthesis", message));
+
+    if quick.open.is_empty() {
+        error(String::from("open is required"));
+    }
+    if !(1..=QUICK_SYNTHESIS_CAP).contains(&quick.batch) {
+        error(format!(
+            "batch must be between 1 and {}",
+            QUICK_SYNTHESIS_CAP
+        ));
+    }
+    if !(quick.item_duration > 0.0 && quick.item_duration <= MAX_ITEM_DURATION) {
+        error(format!(
+            "item_duration must be greater than 0 and at most {}",
+            MAX_ITEM_DURATION
+        ));
+    }
+
+    for (name, presses) in [
+        ("open", &quick.open),
+        ("start", &quick.start),
+        ("close", &quick.close),
+    ] {
+        for (i, press) in presses.iter().enumerate() {
+            if utils::get_enigo_key_code(config.key(&press.key)).is_none() {
+                error(format!(
+                    "{} {}: \"{}\" is not an accepted key",
+                    name,
+                    i + 1,
+                    press.key
+                ));
+            }
+        }
+    }
+}
+
+/// validate_steps checks a list of steps, numbering them from prefix, e.g. "3.1"
+fn validate_steps(
+    config: &Config,
+    steps: &[Action],
+    prefix: &str,
+    errors: &mut Vec<(&'static str, String)>,
+) {
+    for (i, step) in steps.iter().enumerate() {
+        let name = format!("{}{}", prefix, i + 1);
+        match step {
+            Action::StartCraft | Action::StopCraft => {}
+            Action::Key(key) | Action::Macro { key, .. } => {
+                if utils::get_enigo_key_code(config.key(key)).is_none() {
+                    errors.push((
+                        "steps",
+                        format!("{}: \"{}\" is not an accepted key", name, key),
+                    ));
+                }
+            }
+            Action::Wait(seconds) => {
+                if !(0.0..=MAX_WAIT).contains(seconds) {
+                    errors.push((
+                        "steps",
+                        format!("{}: wait must be between 0 and {} seconds", name, MAX_WAIT),
+                    ));
+                }
+            }
+            Action::EnsureBuff { name: buff } => {
+                let key = match buff {
+                    Buff::Food => &config.food,
+                    Buff::Potion => &config.potion,
+                };
+                if key.is_empty() {
+                    errors.push(("steps", format!("{}: {} is not set", name, buff)));
+                }
+            }
+            Action::Every { n, steps } | Action::Repeat { n, steps } => {
+                if *n <= 0 {
+                    errors.push(("steps", format!("{}: n must be greater than 0", name)));
+                }
+                if matches!(step, Action::Repeat { .. }) && *n > MAX_REPEAT {
+                    errors.push((
+                        "steps",
+                        format!("{}: n must be at most {}", name, MAX_REPEAT),
+                    ));
+                }
+                if steps.is_empty() {
+                    errors.push(("steps", format!("{}: steps is empty", name)));
+                }
+                validate_steps(config, steps, &format!("{}.", name), errors);
+            }
+        }
+    }
+}
+
+/// Profile file
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Profile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i32>,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job: Option<String>,
+
+    /// overrides of the job template and the defaults
+    #[serde(flatten)]
+    pub layer: Layer,
+}
+
+impl Profile {
+    /// from_config splits a legacy profile into the parts that differ from the settings
+    fn from_config(config: &Config, settings: &Settings) -> Profile {
+        let differs = |value: &String, global: &Option<String>| {
+            if Some(value) != global.as_ref() {
+                Some(value.clone())
+            } else {
+                None
+            }
+        };
+
+        Profile {
+            id: Some(config.id),
+            name: config.name.clone(),
+            job: None,
+            layer: Layer {
+                amount: Some(config.amount),
+                food: Some(config.food.clone()),
+                food_duration: Some(config.food_duration),
+                potion: Some(config.potion.clone()),
+                macro1: Some(config.macro1.clone()),
+                macro1_duration: Some(config.macro1_duration),
+                macro2: Some(config.macro2.clone()),
+                macro2_duration: Some(config.macro2_duration),
+                macro3: Some(config.macro3.clone()),
+                macro3_duration: Some(config.macro3_duration),
+                start_pause: differs(&config.start_pause, &settings.defaults.start_pause),
+                stop: differs(&config.stop, &settings.defaults.stop),
+                confirm: differs(&config.confirm, &settings.defaults.confirm),
+                cancel: differs(&config.cancel, &settings.defaults.cancel),
+                food_stock: Some(config.food_stock),
+                potion_stock: Some(config.potion_stock),
+                hooks: (!config.hooks.is_empty()).then(|| config.hooks.clone()),
+                script: (!config.script.is_empty()).then(|| config.script.clone()),
+                steps: (!config.steps.is_empty()).then(|| config.steps.clone()),
+                open_synthesis: (!config.open_synthesis.is_empty())
+                    .then(|| config.open_synthesis.clone()),
+                close_synthesis: (!config.close_synthesis.is_empty())
+                    .then(|| config.close_synthesis.clone()),
+                mode: (!config.mode.is_default()).then_some(config.mode),
+                quick_synthesis: (!config.quick_synthesis.is_default())
+                    .then(|| config.quick_synthesis.clone()),
+            },
+        }
+    }
+
+    /// missing_hotkeys returns the hotkeys the profile does not set, which an imported
+    /// profile takes from this machine's settings
+    pub fn missing_hotkeys(&self) -> Vec<&'static str> {
+        [
+            ("start_pause", &self.layer.start_pause),
+            ("stop", &self.layer.stop),
+            ("confirm", &self.layer.confirm),
+            ("cancel", &self.layer.cancel),
+        ]
+        .into_iter()
+        .filter(|(_, key)| key.is_none())
+        .map(|(field, _)| field)
+        .collect()
+    }
+
+    /// share turns a profile into a standalone profile that does not depend on the
+    /// settings or job templates it was resolved with, keeping only the shared
+    /// hotkeys the profile set itself
+    pub fn share(config: &Config) -> Profile {
+        let own = |field: &str, value: &String| {
+            if config.source(field) == Source::Profile {
+                Some(value.clone())
+            } else {
+                None
+            }
+        };
+
+        Profile {
+            id: None,
+            name: config.name.clone(),
+            job: None,
+            layer: Layer {
+                amount: Some(config.amount),
+                food: Some(config.food.clone()),
+                food_duration: Some(config.food_duration),
+                potion: Some(config.potion.clone()),
+                macro1: Some(config.macro1.clone()),
+                macro1_duration: Some(config.macro1_duration),
+                macro2: Some(config.macro2.clone()),
+                macro2_duration: Some(config.macro2_duration),
+                macro3: Some(config.macro3.clone()),
+                macro3_duration: Some(config.macro3_duration),
+                start_pause: own("start_pause", &config.start_pause),
+                stop: own("stop", &config.stop),
+                confirm: own("confirm", &config.confirm),
+                cancel: own("cancel", &config.cancel),
+                food_stock: None,
+                potion_stock: None,
+                hooks: None,
+                script: None,
+                steps: (!config.steps.is_empty()).then(|| config.steps.clone()),
+                open_synthesis: (!config.open_synthesis.is_empty())
+                    .then(|| config.open_synthesis.clone()),
+                close_synthesis: (!config.close_synthesis.is_empty())
+                    .then(|| config.close_synthesis.clone()),
+                mode: (!config.mode.is_default()).then_some(config.mode),
+                quick_synthesis: (!config.quick_synthesis.is_default())
+                    .then(|| config.quick_synthesis.clone()),
+            },
+        }
+    }
+
+    /// resolve combines the profile with its job template and the defaults
+    fn resolve(&self, id: i32, settings: &Settings) -> Config {
+        let mut layers = vec![(Source::Profile, &self.layer)];
+        if let Some(job) = &self.job {
+            if let Some(template) = settings.jobs.get(job) {
+                layers.push((Source::Job(job.clone()), template));
+            }
+        }
+        layers.push((Source::Global, &settings.defaults));
+
+        let mut sources = Sources::new();
+        let s = &mut sources;
+        let l = &layers;
+
+        Config {
+            id,
+            last_used: settings.last_used == id,
+            name: self.name.clone(),
+            amount: resolve_field(l, s, "amount", |l| &l.amount),
+            food: resolve_field(l, s, "food", |l| &l.food),
+            food_duration: resolve_field(l, s, "food_duration", |l| &l.food_duration),
+            potion: resolve_field(l, s, "potion", |l| &l.potion),
+            macro1: resolve_field(l, s, "macro1", |l| &l.macro1),
+            macro1_duration: resolve_field(l, s, "macro1_duration", |l| &l.macro1_duration),
+            macro2: resolve_field(l, s, "macro2", |l| &l.macro2),
+            macro2_duration: resolve_field(l, s, "macro2_duration", |l| &l.macro2_duration),
+            macro3: resolve_field(l, s, "macro3", |l| &l.macro3),
+            macro3_duration: resolve_field(l, s, "macro3_duration", |l| &l.macro3_duration),
+            start_pause: resolve_field(l, s, "start_pause", |l| &l.start_pause),
+            stop: resolve_field(l, s, "stop", |l| &l.stop),
+            confirm: resolve_field(l, s, "confirm", |l| &l.confirm),
+            cancel: resolve_field(l, s, "cancel", |l| &l.cancel),
+            food_stock: resolve_field(l, s, "food_stock", |l| &l.food_stock),
+            potion_stock: resolve_field(l, s, "potion_stock", |l| &l.potion_stock),
+            hooks: resolve_field(l, s, "hooks", |l| &l.hooks),
+            script: resolve_field(l, s, "script", |l| &l.script),
+            steps: resolve_field(l, s, "steps", |l| &l.steps),
+            open_synthesis: resolve_field(l, s, "open_synthesis", |l| &l.open_synthesis),
+            close_synthesis: resolve_field(l, s, "close_synthesis", |l| &l.close_synthesis),
+            mode: resolve_field(l, s, "mode", |l| &l.mode),
+            quick_synthesis: resolve_field(l, s, "quick_synthesis", |l| &l.quick_synthesis),
+            sources,
+            dir: PathBuf::new(),
+        }
+    }
+}
+
+/// resolve_field returns the value of the first layer that sets the field
+fn resolve_field<T: Clone + Default>(
+    layers: &[(Source, &Layer)],
+    sources: &mut Sources,
+    field: &'static str,
+    get: impl Fn(&Layer) -> &Option<T>,
+) -> T {
+    for (source, layer) in layers {
+        if let Some(value) = get(layer) {
+            sources.insert(field, source.clone());
+            return value.clone();
+        }
+    }
+
+    sources.insert(field, Source::Default);
+    T::default()
+}
+
+/// Conflict is what to do when an imported profile has the name of an existing one
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum Conflict {
+    /// import it under a new name, e.g. "Tincture (2)"
+    Rename,
+    /// replace the existing profile, keeping its id
+    Replace,
+    /// leave the existing profile and skip the import
+    Skip,
+}
+
+/// Store is where the profiles are kept
+#[derive(Clone)]
+pub enum Store {
+    /// single JSON file containing every profile, e.g. ~/.xivcrafter.json
+    File(PathBuf),
+    /// directory containing settings.json and a profiles directory
+    Dir(PathBuf),
+}
+
+impl Store {
+    /// open picks the store type from the path
+    pub fn open(path: PathBuf) -> Store {
+        if path.is_file() || path.extension().is_some_and(|ext| ext == "json") {
+            Store::File(path)
+        } else {
+            Store::Dir(path)
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        match self {
+            Store::File(path) | Store::Dir(path) => path,
+        }
+    }
+
+    /// dir returns the directory holding the store, which relative paths in it are
+    /// resolved against
+    pub fn dir(&self) -> &Path {
+        match self {
+            Store::File(path) => path.parent().unwrap_or(Path::new("")),
+            Store::Dir(path) => path,
+        }
+    }
+
+    pub fn settings_path(&self) -> PathBuf {
+        self.path().join(SETTINGS_FILE)
+    }
+
+    pub fn profiles_path(&self) -> PathBuf {
+        self.path().join(PROFILES_DIR)
+    }
+
+    /// exists checks whether the store has been created
+    pub fn exists(&self) -> bool {
+        match self {
+            Store::File(path) => path.exists(),
+            Store::Dir(_) => self.settings_path().exists() || self.profiles_path().exists(),
+        }
+    }
+
+    /// load reads every profile in the store
+    pub fn load(&self) -> io::Result<Vec<Config>> {
+        let mut configs = match self {
+            Store::File(path) => {
+                let file = fs::read_to_string(path)?;
+                serde_json::from_str::<Vec<Config>>(&file)?
+            }
+            Store::Dir(_) => {
+                let settings = self.load_settings()?;
+                self.load_profiles()?
+                    .iter()
+                    .map(|(id, _, profile)| profile.resolve(*id, &settings))
+                    .collect()
+            }
+        };
+
+        if configs.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} has no profiles", self.path().display()),
+            ));
+        }
+
+        for config in &mut configs {
+            config.dir = self.dir().to_path_buf();
+        }
+        Ok(configs)
+    }
+
+    /// load_settings reads settings.json, using the defaults when it is missing
+    pub fn load_settings(&self) -> io::Result<Settings> {
+        let path = self.settings_path();
+        if !path.exists() {
+            return Ok(Settings::default());
+        }
+
+        let file = fs::read_to_string(&path)?;
+        serde_json::from_str(&file).map_err(|e| file_error(&path, e))
+    }
+
+    /// load_profiles reads every file in the profiles directory as (id, path, profile)
+    ///
+    /// Profiles without an id are given the next free one and profiles without a
+    /// name are named after their file, so new files only need to be dropped in. A
+    /// given id is written back to the file, so adding or renaming other files later
+    /// does not change which profile it refers to.
+    pub fn load_profiles(&self) -> io::Result<Vec<(i32, PathBuf, Profile)>> {
+        let dir = self.profiles_path();
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut paths: Vec<PathBuf> = fs::read_dir(&dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+
+        let mut profiles = Vec::new();
+        for path in paths {
+            let file = fs::read_to_string(&path)?;
+            let profile: Profile = serde_json::from_str(&file).map_err(|e| file_error(&path, e))?;
+
+            profiles.push((profile.id, path, profile));
+        }
+
+        // assign ids to new or clashing profiles
+        let mut next_id = profiles
+            .iter()
+            .filter_map(|(id, _, _)| *id)
+            .max()
+            .map_or(0, |id| id + 1);
+        let mut used = Vec::new();
+        let mut profiles: Vec<(i32, PathBuf, Profile)> = profiles
+            .into_iter()
+            .map(|(id, path, mut profile)| {
+                let id = match id {
+                    Some(id) if !used.contains(&id) => id,
+                    _ => {
+                        next_id += 1;
+                        profile.id = Some(next_id - 1);
+                        // a read-only config keeps working, with ids that may shift
+                        let _ = self.write_json(&path, &profile);
+                        next_id - 1
+                    }
+                };
+                used.push(id);
+
+                if profile.name.is_empty() {
+                    if let Some(stem) = path.file_stem() {
+                        profile.name = stem.to_string_lossy().to_string();
+                    }
+                }
+                (id, path, profile)
+            })
+            .collect();
+        profiles.sort_by_key(|(id, _, _)| *id);
+
+        Ok(profiles)
+    }
+
+    /// watch notifies the receiver whenever a file of the store changes
+    ///
+    /// The parent directory of a single-file store is watched so the watch
+    /// survives editors that save by replacing the file.
+    pub fn watch(&self) -> notify::Result<(RecommendedWatcher, mpsc::Receiver<()>)> {
+        let (tx, rx) = mpsc::channel();
+
+        let (dir, file, mode) = match self {
+            Store::File(path) => {
+                let dir = match path.parent() {
+                    Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+                    _ => PathBuf::from("."),
+                };
+                (
+                    dir,
+                    path.file_name().map(|name| name.to_owned()),
+                    RecursiveMode::NonRecursive,
+                )
+            }
+            Store::Dir(path) => (path.clone(), None, RecursiveMode::Recursive),
+        };
+
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| {
+            if let Ok(event) = res {
+                let modified = matches!(
+                    event.kind,
+                    EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+                );
+                let relevant = match &file {
+                    Some(file) => event.paths.iter().any(|p| p.file_name() == Some(file)),
+                    None => true,
+                };
+
+                if modified && relevant {
+                    let _ = tx.send(());
+                }
+            }
+        })?;
+        watcher.watch(&dir, mode)?;
+
+        Ok((watcher, rx))
+    }
+
+    /// init creates the store with a single empty profile
+    pub fn init(&self) -> io::Result<()> {
+        match self {
+            Store::File(path) => {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                self.write_json(path, &[Config::default()])
+            }
+            Store::Dir(_) => {
+                let config = Config::default();
+                let settings = Settings::from_config(&config);
+                let profile = Profile::from_config(&config, &settings);
+
+                fs::create_dir_all(self.profiles_path())?;
+                self.write_json(&self.settings_path(), &settings)?;
+                self.write_json(&self.profiles_path().join("default.json"), &profile)
+            }
+        }
+    }
+
+    /// migrate copies every profile of a legacy config file into the store
+    pub fn migrate(&self, legacy: &Path) -> io::Result<()> {
+        let file = fs::read_to_string(legacy)?;
+        let configs: Vec<Config> = serde_json::from_str(&file)?;
+
+        let current = configs
+            .iter()
+            .find(|c| c.last_used)
+            .or(configs.first())
+            .cloned()
+            .unwrap_or_default();
+        let settings = Settings::from_config(&current);
+
+        fs::create_dir_all(self.profiles_path())?;
+        self.write_json(&self.settings_path(), &settings)?;
+
+        for config in &configs {
+            let profile = Profile::from_config(config, &settings);
+            let path = unique_path(&self.profiles_path(), &config.name, config.id);
+            self.write_json(&path, &profile)?;
+        }
+
+        Ok(())
+    }
+
+    /// set_last_used marks a profile as the one to use on the next start
+    pub fn set_last_used(&self, id: i32) -> io::Result<()> {
+        match self {
+            Store::File(path) => {
+                let file = fs::read_to_string(path)?;
+                let mut configs: Vec<Config> = serde_json::from_str(&file)?;
+                for config in configs.iter_mut() {
+                    config.last_used = config.id == id;
+                }
+                self.write_json(path, &configs)
+            }
+            Store::Dir(_) => {
+                let mut settings = self.load_settings()?;
+                settings.last_used = id;
+                self.write_json(&self.settings_path(), &settings)
+            }
+        }
+    }
+
+    /// import adds a profile to the store under a new id, returning the imported profile
+    /// and the fields left out of it
+    ///
+    /// Hooks and scripts run commands on this machine, so they are never imported from
+    /// a profile someone else shared.
+    pub fn import(
+        &self,
+        mut profile: Profile,
+        conflict: Conflict,
+    ) -> io::Result<(Config, Vec<&'static str>)> {
+        let mut dropped = Vec::new();
+        if profile.layer.hooks.take().is_some() {
+            dropped.push("hooks");
+        }
+        if profile.layer.script.take().is_some() {
+            dropped.push("script");
+        }
+
+        let configs = self.load().unwrap_or_default();
+        let existing = configs.iter().find(|c| c.name == profile.name);
+
+        let id = match (existing, conflict) {
+            (Some(existing), Conflict::Skip) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("profile \"{}\" already exists", existing.name),
+                ));
+            }
+            (Some(existing), Conflict::Replace) => existing.id,
+            (Some(_), Conflict::Rename) => {
+                let mut n = 2;
+                while configs
+                    .iter()
+                    .any(|c| c.name == format!("{} ({})", profile.name, n))
+                {
+                    n += 1;
+                }
+                profile.name = format!("{} ({})", profile.name, n);
+                next_id(&configs)
+            }
+            (None, _) => next_id(&configs),
+        };
+        profile.id = Some(id);
+
+        match self {
+            Store::File(path) => {
+                let file = fs::read_to_string(path)?;
+                let mut configs: Vec<Config> = serde_json::from_str(&file)?;
+
+                // fill the shared hotkeys from the last used profile
+                let current = configs
+                    .iter()
+                    .find(|c| c.last_used)
+                    .or(configs.first())
+                    .cloned()
+                    .unwrap_or_default();
+                let mut config = profile.resolve(id, &Settings::from_config(&current));
+                config.last_used = false;
+
+                match configs.iter_mut().find(|c| c.id == id) {
+                    Some(existing) => {
+                        config.last_used = existing.last_used;
+                        *existing = config.clone();
+                    }
+                    None => configs.push(config.clone()),
+                }
+                self.write_json(path, &configs)?;
+
+                Ok((config, dropped))
+            }
+            Store::Dir(_) => {
+                let path = match self
+                    .load_profiles()?
+                    .into_iter()
+                    .find(|(profile_id, _, _)| *profile_id == id)
+                {
+                    Some((_, path, _)) => path,
+                    None => unique_path(&self.profiles_path(), &profile.name, id),
+                };
+                self.write_json(&path, &profile)?;
+
+                Ok((profile.resolve(id, &self.load_settings()?), dropped))
+            }
+        }
+    }
+
+    /// persist returns the writer for the store's files
+    pub fn persist(&self) -> Persist {
+        let (root, backups) = match self {
+            Store::File(path) => {
+                let mut backups = path.as_os_str().to_owned();
+                backups.push(".backups");
+                let root = path.parent().map(Path::to_path_buf).unwrap_or_default();
+                (root, PathBuf::from(backups))
+            }
+            Store::Dir(path) => (path.clone(), path.join(BACKUPS_DIR)),
+        };
+        let keep = match self {
+            Store::File(_) => None,
+            Store::Dir(_) => self.load_settings().ok().and_then(|s| s.backups),
+        };
+
+        Persist {
+            root,
+            backups,
+            keep: keep.unwrap_or(DEFAULT_BACKUPS),
+        }
+    }
+
+    /// backups returns the backups of the store's files, newest first
+    pub fn backups(&self) -> io::Result<Vec<Backup>> {
+        self.persist().list()
+    }
+
+    /// restore replaces a config file with one of its backups
+    pub fn restore(&self, backup: &Backup) -> io::Result<()> {
+        self.persist().restore(backup)
+    }
+
+    /// write_json atomically writes a value to a file of the store as pretty JSON
+    fn write_json<T: Serialize + ?Sized>(&self, path: &Path, value: &T) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(value)?;
+        self.persist().write(path, json.as_bytes())
+    }
+}
+
+/// default_store returns the config directory, migrating ~/.xivcrafter.json into it
+pub fn default_store() -> io::Result<Store> {
+    let config_dir = dirs::config_dir().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::NotFound, "Unable to locate config directory")
+    })?;
+    let store = Store::Dir(config_dir.join("xivcrafter"));
+
+    if !store.exists() {
+        if let Some(legacy) = dirs::home_dir().map(|home| home.join(".xivcrafter.json")) {
+            if legacy.exists() {
+                store.migrate(&legacy)?;
+            }
+        }
+    }
+
+    Ok(store)
+}
+
+// Helper Functions
+/// next_id returns an id no profile uses yet
+fn next_id(configs: &[Config]) -> i32 {
+    configs.iter().map(|c| c.id).max().map_or(0, |id| id + 1)
+}
+
+/// unique_path returns a path for a new profile file that does not exist yet
+fn unique_path(dir: &Path, name: &str, id: i32) -> PathBuf {
+    let path = dir.join(file_name(name, id));
+    if path.exists() {
+        dir.join(file_name(&format!("{}-{}", name, id), id))
+    } else {
+        path
+    }
+}
+
+/// file_name turns a profile name into a file name
+pub fn file_name(name: &str, id: i32) -> String {
+    let mut slug = String::new();
+    for c in name.to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+
+    if slug.is_empty() {
+        format!("profile-{}.json", id)
+    } else {
+        format!("{}.json", slug)
+    }
+}
+
+/// file_error adds the file path to a parse error
+fn file_error(path: &Path, e: serde_json::Error) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {}", path.display(), e),
+    )
+}
+
+/// validate returns every problem found in a profile as (field, message)
+pub fn validate(config: &Config) -> Vec<(&'static str, String)> {
+    let mut errors = Vec::new();
+
+    if config.amount <= 0 {
+        errors.push(("amount", String::from("must be greater than 0")));
+    }
+
+    if config.food_duration < 0 {
+        errors.push(("food_duration", String::from("must not be negative")));
+    } else if !config.food.is_empty() && config.food_duration == 0 {
+        errors.push(("food_duration", String::from("must be set when using food")));
+    }
+
+    match config.mode {
+        Mode::Macro => {
+            // steps and scripts choose their own macros, if any
+            if config.macro1.is_empty() && config.steps.is_empty() && config.script.is_empty() {
+                errors.push(("macro1", String::from("is required")));
+            }
+        }
+        Mode::QuickSynthesis => {
+            if !config.steps.is_empty() || !config.script.is_empty() {
+                errors.push((
+                    "mode",
+                    String::from("quick_synthesis cannot be used with steps or script"),
+                ));
+            }
+            validate_quick_synthesis(config, &mut errors);
+        }
+    }
+
+    if config.food_stock < 0 {
+        errors.push(("food_stock", String::from("must not be negative")));
+    }
+    if config.potion_stock < 0 {
+        errors.push(("potion_stock", String::from("must not be negative")));
+    }
+    if config
+        .hooks
+        .every
+        .as_ref()
+        .is_some_and(|every| every.n <= 0)
+    {
+        errors.push(("hooks", String::from("every.n must be greater than 0")));
+    }
+    if !config.script.is_empty() {
+        if let Err(e) = Script::load(&config.script_path()) {
+            errors.push(("script", e));
+        }
+        if !config.steps.is_empty() {
+            errors.push(("steps", String::from("cannot be used along with script")));
+        }
+    }
+    validate_steps(config, &config.steps, "", &mut errors);
+
+    for (field, presses) in [
+        ("open_synthesis", &config.open_synthesis),
+        ("close_synthesis", &config.close_synthesis),
+    ] {
+        for (i, press) in presses.iter().enumerate() {
+            if utils::get_enigo_key_code(config.key(&press.key)).is_none() {
+                errors.push((
+                    field,
+                    format!("{}: \"{}\" is not an accepted key", i + 1, press.key),
+                ));
+            }
+        }
+    }
+
+    // hotkeys are read by the TUI, the rest are pressed in the game
+    let keys = [
+        ("food", &config.food, false, false),
+        ("potion", &config.potion, false, false),
+        ("macro1", &config.macro1, false, false),
+        ("macro2", &config.macro2, false, false),
+        ("macro3", &config.macro3, false, false),
+        ("start_pause", &config.start_pause, true, true),
+        ("stop", &config.stop, true, true),
+        ("confirm", &config.confirm, true, false),
+        ("cancel", &config.cancel, true, false),
+    ];
+    for (field, key, required, hotkey) in keys {
+        let accepted = if hotkey {
+            utils::get_crossterm_key_code(key).is_some()
+        } else {
+            utils::get_enigo_key_code(key).is_some()
+        };
+        if key.is_empty() {
+            if required {
+                errors.push((field, String::from("is required")));
+            }
+        } else if !accepted {
+            errors.push((field, format!("\"{}\" is not an accepted key", key)));
+        }
+    }
+
+    errors
+}
+
+/// validate_all checks every profile as well as the file as a whole
+pub fn validate_all(configs: &[Config]) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    for (i, config) in configs.iter().enumerate() {
+        if configs[..i].iter().any(|c| c.id == config.id) {
+            errors.push(format!("profile {}: duplicate id", config.id));
+        }
+
+        for (field, message) in validate(config) {
+            errors.push(format!(
+                "profile {} ({}): {} {}",
+                config.id, config.name, field, message
+            ));
+        }
+    }
+
+    if configs.iter().filter(|c| c.last_used).count() > 1 {
+        errors.push(String::from("more than one profile is marked last_used"));
+    }
+
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Config {
+        Config {
+            food: String::from("f"),
+            potion: String::from("g"),
+            macro1: String::from("1"),
+            macro1_duration: 10,
+            macro2: String::from("2"),
+            macro2_duration: 20,
+            macro3: String::from("3"),
+            macro3_duration: 30,
+            confirm: String::from("c"),
+            cancel: String::from("x"),
+            ..Config::default()
+        }
+    }
+
+    /// presses flattens steps into the keys they press, taking every buff as run out
+    fn presses(config: &Config, steps: &[Action]) -> Vec<String> {
+        let mut keys = Vec::new();
+        for step in steps {
+            match step {
+                Action::StartCraft => keys.extend(
+                    config
+                        .open_synthesis()
+                        .iter()
+                        .map(|press| config.key(&press.key).to_string()),
+                ),
+                Action::StopCraft => keys.extend(
+                    config
+                        .close_synthesis()
+                        .iter()
+                        .map(|press| config.key(&press.key).to_string()),
+                ),
+                Action::Key(key) | Action::Macro { key, .. } => {
+                    keys.push(config.key(key).to_string())
+                }
+                Action::EnsureBuff { name: Buff::Food } => keys.push(config.food.clone()),
+                Action::EnsureBuff { name: Buff::Potion } => keys.push(config.potion.clone()),
+                Action::Wait(_) | Action::Every { .. } | Action::Repeat { .. } => {}
+            }
+        }
+        keys
+    }
+
+    fn step_errors(config: &Config, steps: Vec<Action>) -> Vec<String> {
+        let config = Config {
+            steps,
+            ..config.clone()
+        };
+        validate(&config)
+            .into_iter()
+            .filter(|(field, _)| *field == "steps")
+            .map(|(_, message)| message)
+            .collect()
+    }
+
+    #[test]
+    fn default_steps_keep_the_original_order() {
+        let config = config();
+
+        assert_eq!(
+            config.steps(),
+            vec![
+                Action::StartCraft,
+                Action::EnsureBuff { name: Buff::Food },
+                Action::EnsureBuff { name: Buff::Potion },
+                Action::Macro {
+                    key: String::from("1"),
+                    duration: 10,
+                },
+                Action::Macro {
+                    key: String::from("2"),
+                    duration: 20,
+                },
+                Action::Macro {
+                    key: String::from("3"),
+                    duration: 30,
+                },
+            ]
+        );
+        assert_eq!(
+            presses(&config, &config.steps()),
+            vec!["c", "c", "c", "f", "g", "1", "2", "3"]
+        );
+    }
+
+    #[test]
+    fn default_steps_skip_unset_buffs_and_macros() {
+        let config = Config {
+            food: String::new(),
+            potion: String::new(),
+            macro2: String::new(),
+            ..config()
+        };
+
+        assert_eq!(
+            presses(&config, &config.steps()),
+            vec!["c", "c", "c", "1", "3"]
+        );
+    }
+
+    #[test]
+    fn validate_steps_accepts_the_default_steps() {
+        let config = config();
+
+        assert!(step_errors(&config, config.steps()).is_empty());
+    }
+
+    #[test]
+    fn validate_steps_rejects_unknown_keys() {
+        let errors = step_errors(&config(), vec![Action::Key(String::from("nope"))]);
+
+        assert_eq!(errors, vec!["1: \"nope\" is not an accepted key"]);
+    }
+
+    #[test]
+    fn validate_steps_rejects_invalid_waits() {
+        for seconds in [-1.0, f64::NAN, f64::INFINITY, MAX_WAIT + 1.0] {
+            let errors = step_errors(&config(), vec![Action::Wait(seconds)]);
+
+            assert_eq!(
+                errors,
+                vec!["1: wait must be between 0 and 3600 seconds"],
+                "wait {}",
+                seconds
+            );
+        }
+    }
+
+    #[test]
+    fn validate_steps_rejects_every_zero() {
+        let errors = step_errors(
+            &config(),
+            vec![Action::Every {
+                n: 0,
+                steps: vec![Action::Key(String::from("1"))],
+            }],
+        );
+
+        assert_eq!(errors, vec!["1: n must be greater than 0"]);
+    }
+}
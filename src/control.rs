@@ -0,0 +1,366 @@
+use crate::config::Settings;
+use crate::crafter::Message;
+use crate::progress::Status;
+
+use serde_json::json;
+
+use std::{
+    fmt,
+    io::{self, BufRead, BufReader, Read, Write},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+#[cfg(unix)]
+use std::{
+    env, fs,
+    os::unix::{
+        fs::PermissionsExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::PathBuf,
+};
+
+#[cfg(not(unix))]
+use std::net::{TcpListener, TcpStream};
+
+/// control socket in the runtime directory
+#[cfg(unix)]
+const SOCKET_FILE: &str = "xivcrafter.sock";
+
+/// localhost address listened on where Unix sockets are not available
+#[cfg(not(unix))]
+const ADDRESS: &str = "127.0.0.1:47863";
+
+/// how long to wait for a request or for its answer
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Command is a request sent to a running crafter over the control socket
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    Start,
+    Pause,
+    Stop,
+    Status,
+    SetAmount(i32),
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Command, String> {
+        let mut words = line.split_whitespace();
+        let command = match words.next().unwrap_or("") {
+            "start" => Command::Start,
+            "pause" => Command::Pause,
+            "stop" => Command::Stop,
+            "status" => Command::Status,
+            "set-amount" => {
+                let amount = words
+                    .next()
+                    .and_then(|amount| amount.parse::<i32>().ok())
+                    .filter(|amount| *amount > 0)
+                    .ok_or("set-amount needs an amount greater than 0")?;
+                Command::SetAmount(amount)
+            }
+            "" => return Err(String::from("empty command")),
+            other => return Err(format!("unknown command \"{}\"", other)),
+        };
+
+        match words.next() {
+            Some(extra) => Err(format!("unexpected \"{}\"", extra)),
+            None => Ok(command),
+        }
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Command::Start => write!(f, "start"),
+            Command::Pause => write!(f, "pause"),
+            Command::Stop => write!(f, "stop"),
+            Command::Status => write!(f, "status"),
+            Command::SetAmount(amount) => write!(f, "set-amount {}", amount),
+        }
+    }
+}
+
+/// Request is a command waiting to be answered by the TUI or headless run
+pub struct Request {
+    pub command: Command,
+    reply: mpsc::Sender<String>,
+}
+
+impl Request {
+    /// new creates a request along with the receiver of its answer
+    pub fn new(command: Command) -> (Request, mpsc::Receiver<String>) {
+        let (reply, answer) = mpsc::channel();
+        (Request { command, reply }, answer)
+    }
+
+    /// reply answers the request with a single line
+    pub fn reply(self, text: String) {
+        let _ = self.reply.send(text);
+    }
+}
+
+/// Events passes crafter events on to everyone listening to them
+#[derive(Clone, Default)]
+pub struct Events {
+    listeners: Arc<Mutex<Vec<mpsc::Sender<String>>>>,
+}
+
+impl Events {
+    /// subscribe returns a receiver of every event from now on, as JSON
+    #[cfg(feature = "http")]
+    pub fn subscribe(&self) -> mpsc::Receiver<String> {
+        let (tx, rx) = mpsc::channel();
+        self.listeners.lock().unwrap().push(tx);
+        rx
+    }
+
+    /// publish sends an event to every listener, forgetting the ones that are gone
+    pub fn publish(&self, message: &Message, state: &str) {
+        let mut listeners = self.listeners.lock().unwrap();
+        if listeners.is_empty() {
+            return;
+        }
+
+        let event = json!({
+            "time": message.time.to_rfc3339(),
+            "kind": message.kind.to_string(),
+            "text": message.text,
+            "state": state,
+            "crafted": message.crafted,
+            "food": message.food,
+            "potion": message.potion,
+        })
+        .to_string();
+        listeners.retain(|listener| listener.send(event.clone()).is_ok());
+    }
+}
+
+/// state names the crafter's state
+pub fn state(program_signal: &AtomicBool, crafter_signal: &AtomicBool) -> &'static str {
+    if !program_signal.load(Ordering::Relaxed) {
+        "stopped"
+    } else if crafter_signal.load(Ordering::Relaxed) {
+        "crafting"
+    } else {
+        "paused"
+    }
+}
+
+/// apply carries out start, pause and stop on the crafter's signals, returning the
+/// reply, or None for commands that need the caller's state
+pub fn apply(
+    command: Command,
+    program_signal: &AtomicBool,
+    crafter_signal: &AtomicBool,
+) -> Option<String> {
+    match command {
+        Command::Start => {
+            program_signal.store(true, Ordering::Relaxed);
+            crafter_signal.store(true, Ordering::Relaxed);
+        }
+        Command::Pause => {
+            if !program_signal.load(Ordering::Relaxed) {
+                return Some(String::from("error: the crafter is not running"));
+            }
+            crafter_signal.store(false, Ordering::Relaxed);
+        }
+        Command::Stop => {
+            program_signal.store(false, Ordering::Relaxed);
+            crafter_signal.store(false, Ordering::Relaxed);
+        }
+        Command::Status | Command::SetAmount(_) => return None,
+    }
+
+    Some(format!("ok: {}", state(program_signal, crafter_signal)))
+}
+
+/// Handler is the part of answering requests that depends on the TUI or headless run
+pub trait Handler {
+    /// check returns why the crafter cannot be started, if it cannot
+    fn check(&mut self) -> Result<(), String>;
+    /// set_amount changes the amount to craft and passes it on to the crafter
+    fn set_amount(&mut self, amount: i32) -> Result<(), String>;
+    /// status reports the run's progress
+    fn status(&self, state: &'static str) -> Status;
+}
+
+/// answer carries out a request and replies to it
+pub fn answer(
+    request: Request,
+    handler: &mut impl Handler,
+    program_signal: &AtomicBool,
+    crafter_signal: &AtomicBool,
+) {
+    if request.command == Command::Start && !crafter_signal.load(Ordering::Relaxed) {
+        if let Err(e) = handler.check() {
+            request.reply(format!("error: {}", e));
+            return;
+        }
+    }
+
+    let reply = match apply(request.command, program_signal, crafter_signal) {
+        Some(reply) => reply,
+        None => match request.command {
+            Command::SetAmount(amount) => {
+                if let Err(e) = handler.set_amount(amount) {
+                    request.reply(format!("error: {}", e));
+                    return;
+                }
+                if crafter_signal.load(Ordering::Relaxed) {
+                    format!(
+                        "ok: amount set to {}, applied once the crafter pauses",
+                        amount
+                    )
+                } else {
+                    format!("ok: amount set to {}", amount)
+                }
+            }
+            _ => {
+                let state = state(program_signal, crafter_signal);
+                serde_json::to_string(&handler.status(state)).unwrap()
+            }
+        },
+    };
+    request.reply(reply);
+}
+
+/// Server is the listening control socket, removed when dropped
+pub struct Server {
+    #[cfg(unix)]
+    path: PathBuf,
+}
+
+#[cfg(unix)]
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// socket_path returns where the control socket is created
+#[cfg(unix)]
+pub fn socket_path() -> PathBuf {
+    dirs::runtime_dir()
+        .unwrap_or_else(env::temp_dir)
+        .join(SOCKET_FILE)
+}
+
+/// listen opens the control socket, sending every request it receives to tx
+#[cfg(unix)]
+pub fn listen(tx: mpsc::Sender<Request>) -> io::Result<Server> {
+    let path = socket_path();
+    if path.exists() {
+        // only replace a socket left behind by an instance that is no longer running
+        if UnixStream::connect(&path).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("{} is in use by another instance", path.display()),
+            ));
+        }
+        fs::remove_file(&path)?;
+    }
+
+    let listener = UnixListener::bind(&path)?;
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let _ = stream.set_read_timeout(Some(TIMEOUT));
+            let _ = stream.set_write_timeout(Some(TIMEOUT));
+            if !serve(stream, &tx) {
+                break;
+            }
+        }
+    });
+
+    Ok(Server { path })
+}
+
+#[cfg(not(unix))]
+pub fn listen(tx: mpsc::Sender<Request>) -> io::Result<Server> {
+    let listener = TcpListener::bind(ADDRESS)?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let _ = stream.set_read_timeout(Some(TIMEOUT));
+            let _ = stream.set_write_timeout(Some(TIMEOUT));
+            if !serve(stream, &tx) {
+                break;
+            }
+        }
+    });
+
+    Ok(Server {})
+}
+
+/// serve answers one connection, returning false once nothing is receiving requests
+fn serve<S: Read + Write>(stream: S, tx: &mpsc::Sender<Request>) -> bool {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    if reader.read_line(&mut line).is_err() {
+        return true;
+    }
+
+    let mut running = true;
+    let answer = match line.trim().parse::<Command>() {
+        Ok(command) => {
+            let (request, answer) = Request::new(command);
+            if tx.send(request).is_err() {
+                running = false;
+            }
+            answer
+                .recv_timeout(TIMEOUT)
+                .unwrap_or_else(|_| String::from("error: no answer from xivcrafter"))
+        }
+        Err(e) => format!("error: {}", e),
+    };
+
+    let mut stream = reader.into_inner();
+    let _ = writeln!(stream, "{}", answer);
+    running
+}
+
+/// listen_http starts the HTTP API when it is set up in the settings
+pub fn listen_http(
+    settings: &Settings,
+    tx: mpsc::Sender<Request>,
+    events: &Events,
+) -> io::Result<()> {
+    match &settings.http {
+        None => Ok(()),
+        #[cfg(feature = "http")]
+        Some(http) => crate::http::listen(http, tx, events.clone()),
+        #[cfg(not(feature = "http"))]
+        Some(_) => {
+            let _ = (tx, events);
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "XIVCrafter was built without the http feature",
+            ))
+        }
+    }
+}
+
+/// send sends a command to a running instance and returns its answer
+pub fn send(command: Command) -> io::Result<String> {
+    #[cfg(unix)]
+    let mut stream = UnixStream::connect(socket_path())?;
+    #[cfg(not(unix))]
+    let mut stream = TcpStream::connect(ADDRESS)?;
+
+    stream.set_read_timeout(Some(TIMEOUT * 2))?;
+    writeln!(stream, "{}", command)?;
+
+    let mut answer = String::new();
+    BufReader::new(stream).read_line(&mut answer)?;
+    Ok(answer.trim_end().to_string())
+}
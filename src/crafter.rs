@@ -1,17 +1,19 @@
-use crate::app::App;
-use crate::utils::{self, Config};
+use crate::config::{Action, Buff, Config, KeyPress, Mode, QUICK_SYNTHESIS_CAP};
+use crate::script::Script;
+use crate::utils;
+
+use chrono::{DateTime, Local};
 
 use enigo::{Enigo, KeyboardControllable};
 
 use std::{
-    fs,
-    path::PathBuf,
+    fmt,
     sync::{
         atomic::{AtomicBool, Ordering},
         mpsc, Arc,
     },
     thread,
-    time::{Duration, SystemTime, UNIX_EPOCH},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 /// delay per key press (milliseconds)
@@ -21,89 +23,159 @@ const KEY_DELAY: u64 = 500;
 const ACTION_DELAY: u64 = 2;
 
 /// potion duration (seconds)
-const POTION_DURATION: i64 = 900;
+pub const POTION_DURATION: i64 = 900;
+
+/// Kind is the type of event reported by the crafter
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Status,
+    Countdown,
+    Craft,
+    Macro,
+    Food,
+    Potion,
+    /// output of a profile's hook
+    Hook,
+    /// a step, script or quick synthesis that could not be run, stopping the crafter
+    Error,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 8] = [
+        Kind::Status,
+        Kind::Countdown,
+        Kind::Craft,
+        Kind::Macro,
+        Kind::Food,
+        Kind::Potion,
+        Kind::Hook,
+        Kind::Error,
+    ];
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Kind::Status => "status",
+            Kind::Countdown => "countdown",
+            Kind::Craft => "craft",
+            Kind::Macro => "macro",
+            Kind::Food => "food",
+            Kind::Potion => "potion",
+            Kind::Hook => "hook",
+            Kind::Error => "error",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Step is the macro being run by the crafter
+#[derive(Clone, Copy, Debug)]
+pub struct Step {
+    /// position of the macro in the chain, starting at 1
+    pub index: usize,
+    pub count: usize,
+    /// seconds the macro runs for
+    pub duration: u64,
+    /// seconds of the chain run before this macro
+    pub elapsed: u64,
+    /// seconds the whole chain runs for
+    pub total: u64,
+}
+
+/// Message is an event reported by the crafter along with its progress at the time
+#[derive(Clone, Debug)]
+pub struct Message {
+    pub time: DateTime<Local>,
+    pub kind: Kind,
+    pub text: String,
+    pub step: Option<Step>,
+
+    // Progress
+    pub crafted: i32,
+    pub food: i32,
+    pub potion: i32,
+
+    // Buffs
+    pub food_start_time: i64,
+    pub potion_start_time: i64,
+}
 
 #[derive(Clone)]
 pub struct Crafter {
     // Crafter Settings
+    pub config: Config,
     pub current_amount: i32,
-    pub max_amount: i32,
 
     // Consumables
-    pub food: String,
     pub food_count: i32,
-    pub food_duration: i64,
     pub food_start_time: i64,
-    pub potion: String,
     pub potion_count: i32,
     pub potion_start_time: i64,
-
-    // In-Game Hotkeys
-    pub confirm: String,
-    pub cancel: String,
-    pub macro1: String,
-    pub macro1_duration: u64,
-    pub macro2: String,
-    pub macro2_duration: u64,
-    pub macro3: String,
-    pub macro3_duration: u64,
 }
 
 impl Crafter {
-    pub fn new(path: &PathBuf, last_used: i32) -> Crafter {
-        let file = fs::read_to_string(path).expect("Unable to read file");
-        let json: serde_json::Value = serde_json::from_str(&file).expect("Unable to parse JSON");
-        let configs: Vec<Config> = serde_json::from_value(json).unwrap();
-
+    pub fn new(config: Config) -> Crafter {
         Crafter {
             // Settings
+            config,
             current_amount: 0,
-            max_amount: configs[last_used as usize].amount,
 
             // Consumables
-            food: configs[last_used as usize].food.clone(),
             food_count: 0,
-            food_duration: configs[last_used as usize].food_duration,
             food_start_time: 0,
-            potion: configs[last_used as usize].potion.clone(),
             potion_count: 0,
             potion_start_time: 0,
+        }
+    }
 
-            // In-Game Hotkeys
-            confirm: configs[last_used as usize].confirm.clone(),
-            cancel: configs[last_used as usize].cancel.clone(),
-            macro1: configs[last_used as usize].macro1.clone(),
-            macro1_duration: configs[last_used as usize].macro1_duration,
-            macro2: configs[last_used as usize].macro2.clone(),
-            macro2_duration: configs[last_used as usize].macro2_duration,
-            macro3: configs[last_used as usize].macro3.clone(),
-            macro3_duration: configs[last_used as usize].macro3_duration,
+    /// update changes crafter's values to match the latest config
+    pub fn update(&mut self, updates: &mpsc::Receiver<Config>) {
+        if let Some(config) = updates.try_iter().last() {
+            self.config = config;
         }
     }
 
-    /// update changes app's values to match the config file
-    pub fn update(&mut self, path: &PathBuf, last_used: i32) {
-        let file = fs::read_to_string(path).expect("Unable to read file");
-        let json: serde_json::Value = serde_json::from_str(&file).expect("Unable to parse JSON");
-        let configs: Vec<Config> = serde_json::from_value(json).unwrap();
-
-        // Settings
-        self.max_amount = configs[last_used as usize].amount;
-
-        // Consumables
-        self.food = configs[last_used as usize].food.clone();
-        self.food_duration = configs[last_used as usize].food_duration;
-        self.potion = configs[last_used as usize].potion.clone();
-
-        // In-Game Hotkeys
-        self.macro1 = configs[last_used as usize].macro1.clone();
-        self.macro1_duration = configs[last_used as usize].macro1_duration;
-        self.macro2 = configs[last_used as usize].macro2.clone();
-        self.macro2_duration = configs[last_used as usize].macro2_duration;
-        self.macro3 = configs[last_used as usize].macro3.clone();
-        self.macro3_duration = configs[last_used as usize].macro3_duration;
-        self.confirm = configs[last_used as usize].confirm.clone();
-        self.cancel = configs[last_used as usize].cancel.clone();
+    /// send reports an event along with the current progress
+    pub fn send(&self, tx: &mpsc::Sender<Message>, kind: Kind, text: &str) {
+        self.send_step(tx, kind, text, None);
+    }
+
+    /// send_macro reports which macro is being activated and where it is in the chain
+    fn send_macro(&self, tx: &mpsc::Sender<Message>, number: usize) {
+        let macros = self.config.macros();
+        let index = macros
+            .iter()
+            .position(|(n, _, _)| *n == number)
+            .unwrap_or(0);
+        let step = Step {
+            index: index + 1,
+            count: macros.len(),
+            duration: macros.get(index).map_or(0, |(_, _, duration)| *duration),
+            elapsed: macros[..index]
+                .iter()
+                .map(|(_, _, duration)| duration)
+                .sum(),
+            total: macros.iter().map(|(_, _, duration)| duration).sum(),
+        };
+
+        let text = format!("Activating Macro {}...", number);
+        self.send_step(tx, Kind::Macro, &text, Some(step));
+    }
+
+    fn send_step(&self, tx: &mpsc::Sender<Message>, kind: Kind, text: &str, step: Option<Step>) {
+        tx.send(Message {
+            time: Local::now(),
+            kind,
+            text: String::from(text),
+            step,
+            crafted: self.current_amount,
+            food: self.food_count,
+            potion: self.potion_count,
+            food_start_time: self.food_start_time,
+            potion_start_time: self.potion_start_time,
+        })
+        .unwrap();
     }
 
     // crafter related functions
@@ -123,48 +195,71 @@ impl Crafter {
     }
 
     /// start_craft sets up the crafting action
-    fn start_craft(&mut self, tx: &mpsc::Sender<(i32, i32, i32, String)>) {
-        let msg = String::from("Starting craft...");
-        tx.send((self.current_amount, self.food_count, self.potion_count, msg))
-            .unwrap();
-
-        let mut enigo = Enigo::new();
+    pub fn start_craft(&mut self, tx: &mpsc::Sender<Message>) {
+        self.send(tx, Kind::Craft, "Starting craft...");
 
-        let confirm = utils::get_enigo_key_code(&self.confirm);
-
-        enigo.key_click(confirm.unwrap());
-        thread::sleep(Duration::from_millis(KEY_DELAY));
-        enigo.key_click(confirm.unwrap());
-        thread::sleep(Duration::from_millis(KEY_DELAY));
-        enigo.key_click(confirm.unwrap());
-        thread::sleep(Duration::from_millis(KEY_DELAY));
+        self.press_all(&self.config.open_synthesis());
 
         thread::sleep(Duration::from_secs(ACTION_DELAY));
     }
 
     /// stop_craft closes the crafting action
-    fn stop_craft(&mut self, tx: &mpsc::Sender<(i32, i32, i32, String)>) {
-        let msg = String::from("Stopping craft...");
-        tx.send((self.current_amount, self.food_count, self.potion_count, msg))
-            .unwrap();
+    pub fn stop_craft(&mut self, tx: &mpsc::Sender<Message>) {
+        self.send(tx, Kind::Craft, "Stopping craft...");
+
+        self.press_all(&self.config.close_synthesis());
 
+        thread::sleep(Duration::from_secs(ACTION_DELAY));
+    }
+
+    /// press_all presses each key in turn, waiting its delay after it
+    fn press_all(&self, presses: &[KeyPress]) {
         let mut enigo = Enigo::new();
 
-        let confirm = utils::get_enigo_key_code(&self.confirm);
-        let cancel = utils::get_enigo_key_code(&self.cancel);
+        for press in presses {
+            let key = utils::get_enigo_key_code(self.config.key(&press.key));
+            enigo.key_click(key.unwrap());
+            thread::sleep(Duration::from_millis(press.delay.unwrap_or(KEY_DELAY)));
+        }
+    }
+
+    /// press presses a key, or the profile's confirm or cancel key, and waits KEY_DELAY
+    pub fn press(&self, key: &str) -> Result<(), String> {
+        let key = utils::get_enigo_key_code(self.config.key(key))
+            .ok_or_else(|| format!("\"{}\" is not an accepted key", key))?;
 
-        enigo.key_click(confirm.unwrap());
-        thread::sleep(Duration::from_millis(KEY_DELAY));
-        enigo.key_click(cancel.unwrap());
-        thread::sleep(Duration::from_millis(KEY_DELAY));
-        enigo.key_click(confirm.unwrap());
+        let mut enigo = Enigo::new();
+        enigo.key_click(key);
         thread::sleep(Duration::from_millis(KEY_DELAY));
 
-        thread::sleep(Duration::from_secs(ACTION_DELAY));
+        Ok(())
+    }
+
+    /// activate_macro presses a macro's key and waits for it to finish
+    pub fn activate_macro(&self, tx: &mpsc::Sender<Message>, number: usize) -> Result<(), String> {
+        let (_, key, duration) = self
+            .config
+            .macros()
+            .into_iter()
+            .find(|(n, _, _)| *n == number)
+            .ok_or_else(|| format!("macro{} is not set", number))?;
+
+        self.send_macro(tx, number);
+        self.press(key)?;
+        thread::sleep(Duration::from_secs(duration));
+
+        Ok(())
     }
 
     /// check_food checks to see whether the food buff needs to be renewed
-    fn check_food(&mut self, tx: &mpsc::Sender<(i32, i32, i32, String)>) {
+    pub fn check_food(&mut self, tx: &mpsc::Sender<Message>) {
+        if self.food_due() {
+            self.consume_food(tx);
+        }
+    }
+
+    /// food_due checks whether the food buff has run out or was never started
+    fn food_due(&self) -> bool {
         if self.food_start_time > 0 {
             let now = SystemTime::now()
                 .duration_since(UNIX_EPOCH)
@@ -173,25 +268,28 @@ impl Crafter {
 
             let difference = now - self.food_start_time;
 
-            if difference > self.food_duration {
-                self.consume_food(tx);
-            }
+            difference > self.config.food_duration
         } else {
-            self.consume_food(tx);
+            true
         }
     }
 
-    /// consume_food renews the food buff
-    fn consume_food(&mut self, tx: &mpsc::Sender<(i32, i32, i32, String)>) {
+    /// consume_food leaves the synthesis to renew the food buff
+    fn consume_food(&mut self, tx: &mpsc::Sender<Message>) {
         self.stop_craft(tx);
 
-        let msg = String::from("Consuming food...");
-        tx.send((self.current_amount, self.food_count, self.potion_count, msg))
-            .unwrap();
+        self.use_food(tx);
+
+        self.start_craft(tx);
+    }
+
+    /// use_food renews the food buff
+    fn use_food(&mut self, tx: &mpsc::Sender<Message>) {
+        self.send(tx, Kind::Food, "Consuming food...");
 
         let mut enigo = Enigo::new();
 
-        let food = utils::get_enigo_key_code(&self.food);
+        let food = utils::get_enigo_key_code(&self.config.food);
         self.food_start_time = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .expect("Time went backwards")
@@ -202,12 +300,17 @@ impl Crafter {
         thread::sleep(Duration::from_secs(ACTION_DELAY));
 
         self.increment_food();
-
-        self.start_craft(tx);
     }
 
     /// check_potion checks to see whether the potion buff needs to be renewed
-    fn check_potion(&mut self, tx: &mpsc::Sender<(i32, i32, i32, String)>) {
+    pub fn check_potion(&mut self, tx: &mpsc::Sender<Message>) {
+        if self.potion_due() {
+            self.consume_potion(tx);
+        }
+    }
+
+    /// potion_due checks whether the potion buff has run out or was never started
+    fn potion_due(&self) -> bool {
         if self.potion_start_time > 0 {
             let now = SystemTime::now()
                 .duration_since(UNIX_EPOCH)
@@ -216,25 +319,28 @@ impl Crafter {
 
             let difference = now - self.potion_start_time;
 
-            if difference > POTION_DURATION {
-                self.consume_potion(tx);
-            }
+            difference > POTION_DURATION
         } else {
-            self.consume_potion(tx);
+            true
         }
     }
 
-    /// consume_potion renews the potion buff
-    fn consume_potion(&mut self, tx: &mpsc::Sender<(i32, i32, i32, String)>) {
+    /// consume_potion leaves the synthesis to renew the potion buff
+    fn consume_potion(&mut self, tx: &mpsc::Sender<Message>) {
         self.stop_craft(tx);
 
-        let msg = String::from("Consuming potion...");
-        tx.send((self.current_amount, self.food_count, self.potion_count, msg))
-            .unwrap();
+        self.use_potion(tx);
+
+        self.start_craft(tx);
+    }
+
+    /// use_potion renews the potion buff
+    fn use_potion(&mut self, tx: &mpsc::Sender<Message>) {
+        self.send(tx, Kind::Potion, "Consuming potion...");
 
         let mut enigo = Enigo::new();
 
-        let potion = utils::get_enigo_key_code(&self.potion);
+        let potion = utils::get_enigo_key_code(&self.config.potion);
         self.potion_start_time = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .expect("Time went backwards")
@@ -245,25 +351,262 @@ impl Crafter {
         thread::sleep(Duration::from_secs(ACTION_DELAY));
 
         self.increment_potion();
+    }
 
-        self.start_craft(tx);
+    /// quick_synthesis makes the next batch of items with the game's Quick Synthesis,
+    /// renewing buffs beforehand and reporting each item as it is made
+    ///
+    /// Stopping or pausing the crafter ends the batch early, closing the dialog once the
+    /// item being made is abandoned and counting only the items already made.
+    pub fn quick_synthesis(
+        &mut self,
+        tx: &mpsc::Sender<Message>,
+        program_signal: &AtomicBool,
+        crafter_signal: &AtomicBool,
+    ) -> Result<(), String> {
+        let quick = self.config.quick_synthesis.clone();
+        let item = Duration::try_from_secs_f64(quick.item_duration)
+            .ok()
+            .filter(|item| !item.is_zero())
+            .ok_or_else(|| {
+                format!(
+                    "item_duration must be greater than 0, not {}",
+                    quick.item_duration
+                )
+            })?;
+        // the game turns down amounts over its cap, which would count items never made
+        let batch = quick
+            .batch
+            .min(self.config.amount - self.current_amount)
+            .clamp(1, QUICK_SYNTHESIS_CAP);
+
+        // buffs are renewed between batches, outside of the dialog
+        if !self.config.food.is_empty() {
+            self.send(tx, Kind::Food, "Checking food...");
+
+            if self.food_due() {
+                self.use_food(tx);
+            }
+        }
+        if !self.config.potion.is_empty() {
+            self.send(tx, Kind::Potion, "Checking potion...");
+
+            if self.potion_due() {
+                self.use_potion(tx);
+            }
+        }
+
+        let text = format!("Starting quick synthesis of {}...", batch);
+        self.send(tx, Kind::Craft, &text);
+
+        self.press_all(&quick.open);
+        for digit in batch.to_string().chars() {
+            self.press(&digit.to_string())?;
+        }
+        self.press_all(&quick.start);
+
+        let running =
+            || program_signal.load(Ordering::Relaxed) && crafter_signal.load(Ordering::Relaxed);
+        let mut made = 0;
+        'batch: for _ in 0..batch {
+            let start = Instant::now();
+            while start.elapsed() < item {
+                if !running() {
+                    break 'batch;
+                }
+                thread::sleep((item - start.elapsed().min(item)).min(crate::app::TICK_RATE));
+            }
+
+            made += 1;
+            self.increment_amount();
+            self.send(tx, Kind::Craft, "Craft complete");
+        }
+
+        if made < batch {
+            let text = format!("Stopping quick synthesis after {} of {}...", made, batch);
+            self.send(tx, Kind::Craft, &text);
+        }
+        self.send(tx, Kind::Craft, "Closing quick synthesis...");
+        self.press_all(&quick.close);
+
+        thread::sleep(Duration::from_secs(ACTION_DELAY));
+        Ok(())
+    }
+
+    /// craft runs the profile's steps for one craft
+    pub fn craft(&mut self, tx: &mpsc::Sender<Message>) -> Result<(), String> {
+        let steps = self.config.steps();
+        self.run_steps(tx, &steps)
     }
+
+    /// run_steps runs a list of steps, stopping at the first that cannot be run
+    fn run_steps(&mut self, tx: &mpsc::Sender<Message>, steps: &[Action]) -> Result<(), String> {
+        // macros are reported by where they are in this list
+        let macros: Vec<u64> = steps
+            .iter()
+            .filter_map(|step| match step {
+                Action::Macro { duration, .. } => Some(*duration),
+                _ => None,
+            })
+            .collect();
+        let mut index = 0;
+
+        for step in steps {
+            match step {
+                Action::StartCraft => self.start_craft(tx),
+                Action::StopCraft => self.stop_craft(tx),
+                Action::Key(key) => self.press(key)?,
+                Action::Wait(seconds) => {
+                    let wait = Duration::try_from_secs_f64(*seconds)
+                        .map_err(|_| format!("cannot wait {} seconds", seconds))?;
+                    thread::sleep(wait);
+                }
+                Action::Macro { key, duration } => {
+                    // a macro using one of the profile's macro keys is reported as that macro
+                    let number = self
+                        .config
+                        .macros()
+                        .into_iter()
+                        .find(|(_, k, d)| k == key && d == duration)
+                        .map_or(index + 1, |(number, _, _)| number);
+                    let progress = Step {
+                        index: index + 1,
+                        count: macros.len(),
+                        duration: *duration,
+                        elapsed: macros[..index].iter().sum(),
+                        total: macros.iter().sum(),
+                    };
+                    let text = format!("Activating Macro {}...", number);
+                    self.send_step(tx, Kind::Macro, &text, Some(progress));
+
+                    self.press(key)?;
+                    thread::sleep(Duration::from_secs(*duration));
+                    index += 1;
+                }
+                Action::EnsureBuff { name } if self.buff_key(*name).is_none() => {
+                    return Err(format!("{} is not an accepted key", name));
+                }
+                Action::EnsureBuff { name: Buff::Food } => {
+                    self.send(tx, Kind::Food, "Checking food...");
+
+                    self.check_food(tx);
+                }
+                Action::EnsureBuff { name: Buff::Potion } => {
+                    self.send(tx, Kind::Potion, "Checking potion...");
+
+                    self.check_potion(tx);
+                }
+                Action::Every { n, .. } if *n <= 0 => {
+                    return Err(String::from("every needs n greater than 0"));
+                }
+                Action::Every { n, steps } => {
+                    // counting the craft being made
+                    if (self.current_amount + 1) % n == 0 {
+                        self.run_steps(tx, steps)?;
+                    }
+                }
+                Action::Repeat { n, steps } => {
+                    for _ in 0..*n {
+                        self.run_steps(tx, steps)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// buff_key returns the key for a buff's consumable, None when it is not usable
+    fn buff_key(&self, buff: Buff) -> Option<enigo::Key> {
+        let key = match buff {
+            Buff::Food => &self.config.food,
+            Buff::Potion => &self.config.potion,
+        };
+        utils::get_enigo_key_code(key)
+    }
+}
+
+/// craft_duration estimates how long one craft takes from starting it until it is
+/// counted, None when it is too long to estimate
+pub fn craft_duration(config: &Config) -> Option<Duration> {
+    if config.mode == Mode::QuickSynthesis {
+        return quick_synthesis_duration(config);
+    }
+
+    steps_duration(config, &config.steps())?.checked_add(Duration::from_secs(ACTION_DELAY))
+}
+
+/// quick_synthesis_duration estimates the time per item with Quick Synthesis, spreading
+/// opening and closing the dialog over a full batch
+fn quick_synthesis_duration(config: &Config) -> Option<Duration> {
+    let quick = &config.quick_synthesis;
+    let batch = quick.batch.clamp(1, QUICK_SYNTHESIS_CAP) as u32;
+
+    let digits = batch.to_string().len() as u64;
+    let overhead = presses_duration(&quick.open)?
+        .checked_add(Duration::from_millis(KEY_DELAY * digits))?
+        .checked_add(presses_duration(&quick.start)?)?
+        .checked_add(presses_duration(&quick.close)?)?
+        .saturating_sub(Duration::from_secs(ACTION_DELAY * 2));
+
+    let item = Duration::try_from_secs_f64(quick.item_duration.max(0.0)).ok()?;
+    item.checked_add(overhead / batch)
 }
 
+/// steps_duration estimates how long a list of steps takes, spreading steps run on
+/// every nth craft over the crafts in between
+fn steps_duration(config: &Config, steps: &[Action]) -> Option<Duration> {
+    steps.iter().try_fold(Duration::ZERO, |total, step| {
+        let duration = match step {
+            Action::StartCraft => presses_duration(&config.open_synthesis())?,
+            Action::StopCraft => presses_duration(&config.close_synthesis())?,
+            Action::Key(_) => Duration::from_millis(KEY_DELAY),
+            Action::Wait(seconds) => Duration::try_from_secs_f64(seconds.max(0.0)).ok()?,
+            Action::Macro { duration, .. } => {
+                Duration::from_millis(KEY_DELAY).checked_add(Duration::from_secs(*duration))?
+            }
+            Action::EnsureBuff { .. } => Duration::ZERO,
+            Action::Every { n, steps } => steps_duration(config, steps)? / (*n).max(1) as u32,
+            Action::Repeat { n, steps } => {
+                steps_duration(config, steps)?.checked_mul((*n).max(0) as u32)?
+            }
+        };
+        total.checked_add(duration)
+    })
+}
+
+/// presses_duration estimates how long opening or closing the synthesis window takes
+fn presses_duration(presses: &[KeyPress]) -> Option<Duration> {
+    presses
+        .iter()
+        .map(|press| Duration::from_millis(press.delay.unwrap_or(KEY_DELAY)))
+        .try_fold(Duration::from_secs(ACTION_DELAY), Duration::checked_add)
+}
+
+/// craft runs the crafter in a new thread, applying config sent through updates
+/// whenever it is waiting
+///
+/// Setting close_signal makes the crafter close the crafting log with stop_craft once
+/// it stops or pauses, clearing close_signal when done.
 pub fn craft(
-    app: &App,
+    config: Config,
+    updates: mpsc::Receiver<Config>,
     program_signal: Arc<AtomicBool>,
     crafter_signal: Arc<AtomicBool>,
-) -> mpsc::Receiver<(i32, i32, i32, String)> {
-    let path = app.config.clone();
-    let last_used = app.last_used;
-
+    close_signal: Arc<AtomicBool>,
+) -> mpsc::Receiver<Message> {
     let (tx, rx) = mpsc::channel();
 
     thread::spawn(move || {
+        let mut config = config;
+
         loop {
+            if let Some(latest) = updates.try_iter().last() {
+                config = latest;
+            }
+
             let mut paused = false;
-            let mut crafter = Crafter::new(&path, last_used);
+            let mut crafter = Crafter::new(config.clone());
 
             while program_signal.load(Ordering::Relaxed) {
                 if crafter.current_amount == 0 && !paused {
@@ -271,6 +614,19 @@ pub fn craft(
                     countdown(&crafter, &tx);
                 }
 
+                // the script is loaded on every start or resume, picking up changes to it
+                let mut script = None;
+                if crafter_signal.load(Ordering::Relaxed) && !crafter.config.script.is_empty() {
+                    match Script::load(&crafter.config.script_path()) {
+                        Ok(loaded) => script = Some(loaded),
+                        Err(e) => {
+                            crafter.send(&tx, Kind::Error, &format!("Script error: {}", e));
+                            program_signal.store(false, Ordering::Relaxed);
+                            crafter_signal.store(false, Ordering::Relaxed);
+                        }
+                    }
+                }
+
                 while crafter_signal.load(Ordering::Relaxed) {
                     if paused {
                         // Countdown to allow time for user to focus FFXIV
@@ -279,108 +635,56 @@ pub fn craft(
                         paused = false;
                     }
 
-                    let mut enigo = Enigo::new();
-
-                    crafter.start_craft(&tx);
-
-                    // check food
-                    if !crafter.food.is_empty() {
-                        let msg = String::from("Checking food...");
-                        tx.send((
-                            crafter.current_amount,
-                            crafter.food_count,
-                            crafter.potion_count,
-                            msg,
-                        ))
-                        .unwrap();
-
-                        crafter.check_food(&tx);
-                    }
-
-                    // check potion
-                    if !crafter.potion.is_empty() {
-                        let msg: String = String::from("Checking potion...");
-                        tx.send((
-                            crafter.current_amount,
-                            crafter.food_count,
-                            crafter.potion_count,
-                            msg,
-                        ))
-                        .unwrap();
-
-                        crafter.check_potion(&tx);
-                    }
-
-                    // activate macro 1
-                    let msg = String::from("Activating Macro 1...");
-                    tx.send((
-                        crafter.current_amount,
-                        crafter.food_count,
-                        crafter.potion_count,
-                        msg,
-                    ))
-                    .unwrap();
-                    let macro1 = utils::get_enigo_key_code(&crafter.macro1);
-                    enigo.key_click(macro1.unwrap());
-                    thread::sleep(Duration::from_millis(KEY_DELAY));
-                    thread::sleep(Duration::from_secs(crafter.macro1_duration));
-
-                    // activate macro 2
-                    if !crafter.macro2.is_empty() {
-                        let msg = String::from("Activating Macro 2...");
-                        tx.send((
-                            crafter.current_amount,
-                            crafter.food_count,
-                            crafter.potion_count,
-                            msg,
-                        ))
-                        .unwrap();
-                        let macro2 = utils::get_enigo_key_code(&crafter.macro2);
-                        enigo.key_click(macro2.unwrap());
-                        thread::sleep(Duration::from_millis(KEY_DELAY));
-                        thread::sleep(Duration::from_secs(crafter.macro2_duration));
+                    if crafter.config.mode == Mode::QuickSynthesis {
+                        if let Err(e) =
+                            crafter.quick_synthesis(&tx, &program_signal, &crafter_signal)
+                        {
+                            let text = format!("Quick synthesis error: {}", e);
+                            crafter.send(&tx, Kind::Error, &text);
+                            program_signal.store(false, Ordering::Relaxed);
+                            crafter_signal.store(false, Ordering::Relaxed);
+                        }
+                    } else {
+                        let result = match &script {
+                            Some(script) => script
+                                .craft(&mut crafter, &tx, &program_signal)
+                                .map_err(|e| format!("Script error: {}", e)),
+                            None => crafter.craft(&tx).map_err(|e| format!("Step error: {}", e)),
+                        };
+                        if let Err(e) = result {
+                            // a script ended by stopping the crafter is not an error
+                            if program_signal.load(Ordering::Relaxed) {
+                                crafter.send(&tx, Kind::Error, &e);
+                            }
+                            program_signal.store(false, Ordering::Relaxed);
+                            crafter_signal.store(false, Ordering::Relaxed);
+                            continue;
+                        }
+
+                        thread::sleep(Duration::from_secs(ACTION_DELAY));
+
+                        crafter.increment_amount();
+                        crafter.send(&tx, Kind::Craft, "Craft complete");
                     }
 
-                    // activate macro 3
-                    if !crafter.macro3.is_empty() {
-                        let msg = String::from("Activating Macro 3...");
-                        tx.send((
-                            crafter.current_amount,
-                            crafter.food_count,
-                            crafter.potion_count,
-                            msg,
-                        ))
-                        .unwrap();
-                        let macro3 = utils::get_enigo_key_code(&crafter.macro3);
-                        enigo.key_click(macro3.unwrap());
-                        thread::sleep(Duration::from_millis(KEY_DELAY));
-                        thread::sleep(Duration::from_secs(crafter.macro3_duration));
-                    }
-
-                    crafter.increment_amount();
-                    if crafter.current_amount >= crafter.max_amount {
+                    if crafter.current_amount >= crafter.config.amount {
                         program_signal.store(false, Ordering::Relaxed);
                         crafter_signal.store(false, Ordering::Relaxed);
                     }
-
-                    thread::sleep(Duration::from_secs(ACTION_DELAY));
                 }
 
-                let msg = String::from("Waiting...");
-                tx.send((
-                    crafter.current_amount,
-                    crafter.food_count,
-                    crafter.potion_count,
-                    msg,
-                ))
-                .unwrap();
-                if !paused {
-                    crafter.update(&path, last_used);
+                if close_signal.load(Ordering::Relaxed) {
+                    crafter.stop_craft(&tx);
+                    close_signal.store(false, Ordering::Relaxed);
                 }
+
+                crafter.send(&tx, Kind::Status, "Waiting...");
+                crafter.update(&updates);
                 paused = true;
 
                 thread::sleep(crate::app::TICK_RATE);
             }
+            config = crafter.config;
 
             thread::sleep(crate::app::TICK_RATE);
         }
@@ -390,63 +694,21 @@ pub fn craft(
 }
 
 // Craft Helper Functions
-fn countdown(crafter: &Crafter, tx: &mpsc::Sender<(i32, i32, i32, String)>) {
-    let msg = String::from("Starting in 5...");
-    tx.send((
-        crafter.current_amount,
-        crafter.food_count,
-        crafter.potion_count,
-        msg,
-    ))
-    .unwrap();
+fn countdown(crafter: &Crafter, tx: &mpsc::Sender<Message>) {
+    crafter.send(tx, Kind::Countdown, "Starting in 5...");
     thread::sleep(Duration::from_secs(1));
 
-    let msg = String::from("Starting in 4...");
-    tx.send((
-        crafter.current_amount,
-        crafter.food_count,
-        crafter.potion_count,
-        msg,
-    ))
-    .unwrap();
+    crafter.send(tx, Kind::Countdown, "Starting in 4...");
     thread::sleep(Duration::from_secs(1));
 
-    let msg = String::from("Starting in 3...");
-    tx.send((
-        crafter.current_amount,
-        crafter.food_count,
-        crafter.potion_count,
-        msg,
-    ))
-    .unwrap();
+    crafter.send(tx, Kind::Countdown, "Starting in 3...");
     thread::sleep(Duration::from_secs(1));
 
-    let msg = String::from("Starting in 2...");
-    tx.send((
-        crafter.current_amount,
-        crafter.food_count,
-        crafter.potion_count,
-        msg,
-    ))
-    .unwrap();
+    crafter.send(tx, Kind::Countdown, "Starting in 2...");
     thread::sleep(Duration::from_secs(1));
 
-    let msg = String::from("Starting in 1...");
-    tx.send((
-        crafter.current_amount,
-        crafter.food_count,
-        crafter.potion_count,
-        msg,
-    ))
-    .unwrap();
+    crafter.send(tx, Kind::Countdown, "Starting in 1...");
     thread::sleep(Duration::from_secs(1));
 
-    let msg = String::from("Starting in 0...");
-    tx.send((
-        crafter.current_amount,
-        crafter.food_count,
-        crafter.potion_count,
-        msg,
-    ))
-    .unwrap();
+    crafter.send(tx, Kind::Countdown, "Starting in 0...");
 }
@@ -0,0 +1,272 @@
+use crate::config::Config;
+use crate::crafter::{Kind, Message};
+
+use chrono::Local;
+
+use serde_json::{json, Value};
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    process::{Command, Stdio},
+    sync::mpsc,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// seconds a hook may run when the profile does not set a timeout
+const DEFAULT_TIMEOUT: u64 = 30;
+
+/// how often to check whether a hook has exited
+const POLL: Duration = Duration::from_millis(100);
+
+/// longest to wait on exit for hooks still running, which are then left to finish alone
+const EXIT_WAIT: Duration = Duration::from_secs(2);
+
+/// Runner runs a profile's hooks on crafter events
+///
+/// Hooks run in the background, apart from the crafter thread, so they never delay
+/// its key presses. Their output is sent as hook events to the receiver returned by new.
+pub struct Runner {
+    output: mpsc::Sender<Message>,
+
+    /// whether a run has started and not yet finished
+    running: bool,
+    /// last event from the crafter, for the progress passed to hooks
+    last: Option<Message>,
+
+    /// hooks still running
+    pending: Vec<JoinHandle<()>>,
+}
+
+impl Runner {
+    pub fn new() -> (Runner, mpsc::Receiver<Message>) {
+        let (output, rx) = mpsc::channel();
+        let runner = Runner {
+            output,
+            running: false,
+            last: None,
+            pending: Vec::new(),
+        };
+        (runner, rx)
+    }
+
+    /// message runs the hooks for crafts, food and potions since the last event
+    pub fn message(&mut self, message: &Message, config: &Config) {
+        let (crafted, food, potion) = match &self.last {
+            Some(last) => (last.crafted, last.food, last.potion),
+            None => (0, 0, 0),
+        };
+        self.last = Some(message.clone());
+
+        if message.kind == Kind::Error {
+            self.error(config, &message.text);
+        }
+
+        if let Some(every) = &config.hooks.every {
+            if message.crafted > crafted && every.n > 0 && message.crafted % every.n == 0 {
+                self.run(config, "every", &every.command, "");
+            }
+        }
+        if message.food > food {
+            if let Some(command) = &config.hooks.food {
+                self.run(config, "food", command, "");
+            }
+        }
+        if message.potion > potion {
+            if let Some(command) = &config.hooks.potion {
+                self.run(config, "potion", command, "");
+            }
+        }
+    }
+
+    /// state runs the run start and finish hooks when the crafter starts or stops
+    pub fn state(&mut self, config: &Config, state: &str) {
+        if !self.running && state != "stopped" {
+            self.running = true;
+            self.last = None;
+            if let Some(command) = &config.hooks.run_start {
+                self.run(config, "run_start", command, "");
+            }
+        } else if self.running && state == "stopped" {
+            self.running = false;
+            let crafted = self.last.as_ref().map_or(0, |last| last.crafted);
+            let result = if crafted >= config.amount {
+                "complete"
+            } else {
+                "stopped"
+            };
+            if let Some(command) = &config.hooks.run_finish {
+                self.run(config, "run_finish", command, result);
+            }
+        }
+    }
+
+    /// error runs the error hook
+    pub fn error(&mut self, config: &Config, text: &str) {
+        if let Some(command) = &config.hooks.error {
+            self.run(config, "error", command, text);
+        }
+    }
+
+    /// run starts a hook, passing the event as XIVCRAFTER_* environment variables and
+    /// as JSON on stdin
+    fn run(&mut self, config: &Config, event: &str, command: &str, text: &str) {
+        let last = self.last.clone().unwrap_or_else(|| Message {
+            time: Local::now(),
+            kind: Kind::Hook,
+            text: String::new(),
+            step: None,
+            crafted: 0,
+            food: 0,
+            potion: 0,
+            food_start_time: 0,
+            potion_start_time: 0,
+        });
+        let details = json!({
+            "event": event,
+            "profile": config.name,
+            "crafted": last.crafted,
+            "amount": config.amount,
+            "food": last.food,
+            "potion": last.potion,
+            "text": text,
+        });
+
+        let hook = Hook {
+            event: event.to_string(),
+            command: command.to_string(),
+            timeout: Duration::from_secs(config.hooks.timeout.unwrap_or(DEFAULT_TIMEOUT)),
+            details,
+            output: self.output.clone(),
+            last,
+        };
+        self.pending.retain(|handle| !handle.is_finished());
+        self.pending.push(thread::spawn(move || hook.run()));
+    }
+}
+
+impl Drop for Runner {
+    /// drop gives hooks still running a moment to finish without holding up exit, since
+    /// the TUI only restores the terminal afterwards
+    fn drop(&mut self) {
+        let deadline = Instant::now() + EXIT_WAIT;
+        while self.pending.iter().any(|handle| !handle.is_finished()) && Instant::now() < deadline {
+            thread::sleep(POLL);
+        }
+    }
+}
+
+/// Hook is a command being run for an event
+struct Hook {
+    event: String,
+    command: String,
+    timeout: Duration,
+    details: Value,
+    output: mpsc::Sender<Message>,
+    /// progress when the hook was started
+    last: Message,
+}
+
+impl Hook {
+    fn run(self) {
+        let mut shell = if cfg!(windows) {
+            let mut shell = Command::new("cmd");
+            shell.arg("/C");
+            shell
+        } else {
+            let mut shell = Command::new("sh");
+            shell.arg("-c");
+            shell
+        };
+        shell
+            .arg(&self.command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if let Value::Object(details) = &self.details {
+            for (key, value) in details {
+                let value = match value {
+                    Value::String(value) => value.clone(),
+                    value => value.to_string(),
+                };
+                shell.env(format!("XIVCRAFTER_{}", key.to_uppercase()), value);
+            }
+        }
+
+        let mut child = match shell.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                self.send(format!("unable to start: {}", e));
+                return;
+            }
+        };
+
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = writeln!(stdin, "{}", self.details);
+        }
+
+        // the readers finish once the hook and anything it started close their output
+        let readers = [
+            child.stdout.take().map(|stdout| self.forward(stdout)),
+            child.stderr.take().map(|stderr| self.forward(stderr)),
+        ];
+
+        let start = Instant::now();
+        loop {
+            match child.try_wait() {
+                Ok(Some(status)) => {
+                    // output still held open by something the hook started is left behind
+                    while readers.iter().flatten().any(|reader| !reader.is_finished())
+                        && start.elapsed() < self.timeout
+                    {
+                        thread::sleep(POLL);
+                    }
+                    if !status.success() {
+                        self.send(format!("exited with {}", status));
+                    }
+                    return;
+                }
+                Ok(None) if start.elapsed() >= self.timeout => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    self.send(format!("killed after {} seconds", self.timeout.as_secs()));
+                    return;
+                }
+                Ok(None) => thread::sleep(POLL),
+                Err(e) => {
+                    self.send(format!("unable to wait for hook: {}", e));
+                    return;
+                }
+            }
+        }
+    }
+
+    /// forward sends each line a hook writes to the log
+    fn forward<R: Read + Send + 'static>(&self, reader: R) -> JoinHandle<()> {
+        let output = self.output.clone();
+        let event = self.event.clone();
+        let last = self.last.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(reader).lines().map_while(Result::ok) {
+                let _ = output.send(hook_message(&last, &event, &line));
+            }
+        })
+    }
+
+    fn send(&self, text: String) {
+        let _ = self
+            .output
+            .send(hook_message(&self.last, &self.event, &text));
+    }
+}
+
+/// hook_message turns a line of hook output into an event for the log
+fn hook_message(last: &Message, event: &str, text: &str) -> Message {
+    Message {
+        time: Local::now(),
+        kind: Kind::Hook,
+        text: format!("{}: {}", event, text),
+        step: None,
+        ..last.clone()
+    }
+}
@@ -0,0 +1,185 @@
+use crate::config::Http;
+use crate::control::{Command, Events, Request, TIMEOUT};
+
+use serde_json::json;
+
+use std::{
+    io::{self, Write},
+    net::{SocketAddr, ToSocketAddrs},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use tiny_http::{Header, Method, Response, Server};
+
+/// dashboard served at /
+const DASHBOARD: &str = include_str!("dashboard.html");
+
+/// how often to write to an idle event stream, so closed connections are noticed
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// listen starts the HTTP API, sending control requests to tx
+///
+/// A token is always required, even on localhost, since any web page open in a browser
+/// on this machine can send requests to it.
+pub fn listen(settings: &Http, tx: mpsc::Sender<Request>, events: Events) -> io::Result<()> {
+    let token = match &settings.token {
+        Some(token) if !token.is_empty() => token.clone(),
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "http.token is required",
+            ))
+        }
+    };
+
+    let addresses: Vec<SocketAddr> = settings.address.to_socket_addrs()?.collect();
+    let server = Server::http(&addresses[..]).map_err(io::Error::other)?;
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let tx = tx.clone();
+            let events = events.clone();
+            let token = token.clone();
+            thread::spawn(move || handle(request, &token, &tx, &events));
+        }
+    });
+
+    Ok(())
+}
+
+/// handle answers one HTTP request
+fn handle(request: tiny_http::Request, token: &str, tx: &mpsc::Sender<Request>, events: &Events) {
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+
+    if !authorized(&request, query, token) {
+        respond(request, 401, "application/json", error("unauthorized"));
+        return;
+    }
+
+    let command = match (request.method(), path) {
+        (Method::Get, "/") => {
+            respond(
+                request,
+                200,
+                "text/html; charset=utf-8",
+                DASHBOARD.to_string(),
+            );
+            return;
+        }
+        (Method::Get, "/api/events") => {
+            stream(request, events);
+            return;
+        }
+        (Method::Get, "/api/status") => Command::Status,
+        (Method::Post, "/api/start") => Command::Start,
+        (Method::Post, "/api/pause") => Command::Pause,
+        (Method::Post, "/api/stop") => Command::Stop,
+        _ => {
+            respond(request, 404, "application/json", error("not found"));
+            return;
+        }
+    };
+
+    let (control, answer) = Request::new(command);
+    let answer = match tx.send(control) {
+        Ok(_) => answer.recv_timeout(TIMEOUT).ok(),
+        Err(_) => None,
+    };
+
+    let (status, body) = match answer {
+        None => (503, error("no answer from xivcrafter")),
+        Some(answer) if command == Command::Status => (200, answer),
+        Some(answer) => match answer.strip_prefix("error: ") {
+            Some(message) => (409, error(message)),
+            None => (
+                200,
+                json!({ "state": answer.trim_start_matches("ok: ") }).to_string(),
+            ),
+        },
+    };
+    respond(request, status, "application/json", body);
+}
+
+/// authorized checks the token sent as a bearer token or in the query string
+fn authorized(request: &tiny_http::Request, query: &str, token: &str) -> bool {
+    let bearer = request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Authorization"))
+        .and_then(|header| header.value.as_str().strip_prefix("Bearer "));
+    let param = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("token="))
+        .map(decode);
+
+    bearer == Some(token) || param.as_deref() == Some(token)
+}
+
+/// decode undoes the percent-encoding of a query string value
+fn decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (b'+', _) => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// stream sends crafter events as server-sent events until the client goes away
+fn stream(request: tiny_http::Request, events: &Events) {
+    let rx = events.subscribe();
+    let mut writer = request.into_writer();
+
+    let head = "HTTP/1.1 200 OK\r\n\
+                Content-Type: text/event-stream\r\n\
+                Cache-Control: no-cache\r\n\
+                Connection: close\r\n\r\n";
+    if writer.write_all(head.as_bytes()).is_err() || writer.flush().is_err() {
+        return;
+    }
+
+    loop {
+        let chunk = match rx.recv_timeout(KEEP_ALIVE) {
+            Ok(event) => format!("data: {}\n\n", event),
+            Err(mpsc::RecvTimeoutError::Timeout) => String::from(": keep-alive\n\n"),
+            Err(mpsc::RecvTimeoutError::Disconnected) => return,
+        };
+        if writer.write_all(chunk.as_bytes()).is_err() || writer.flush().is_err() {
+            return;
+        }
+    }
+}
+
+fn respond(request: tiny_http::Request, status: u16, content_type: &str, body: String) {
+    let header = Header::from_bytes("Content-Type", content_type).unwrap();
+    let response = Response::from_string(body)
+        .with_status_code(status)
+        .with_header(header);
+    let _ = request.respond(response);
+}
+
+fn error(message: &str) -> String {
+    json!({ "error": message }).to_string()
+}
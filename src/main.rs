@@ -1,77 +1,106 @@
 mod app;
+mod cli;
+mod config;
+mod control;
 mod crafter;
+mod hooks;
+#[cfg(feature = "http")]
+mod http;
+mod notification;
+mod persist;
+mod progress;
+mod script;
+mod share;
+mod status_file;
+mod theme;
 mod ui;
 mod utils;
+mod view;
 
 use crate::app::{run_app, App};
+use crate::cli::{Cli, Command};
+use crate::config::Store;
+
+use clap::Parser;
 
 use crossterm::{
-    event::{DisableMouseCapture, EnableMouseCapture},
+    event::{DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 
-use std::{
-    error::Error,
-    fs::File,
-    io::{stdout, Write},
-};
+use std::{error::Error, io::stdout, process};
 
 use tui::{backend::CrosstermBackend, Terminal};
 
-fn main() -> Result<(), Box<dyn Error>> {
-    // get home directory path
-    let home_dir = dirs::home_dir();
-    if home_dir.is_none() {
-        panic!("ERROR cannot locate home directory")
+fn main() {
+    if let Err(err) = start() {
+        eprintln!("Error: {}", err);
+        process::exit(1);
+    }
+}
+
+fn start() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+
+    if let Some(Command::Ctl { command }) = &cli.command {
+        return cli::ctl(*command);
+    }
+
+    // get config location, migrating ~/.xivcrafter.json if needed
+    let store = match &cli.config {
+        Some(path) => Store::open(path.clone()),
+        None => config::default_store()?,
+    };
+
+    match &cli.command {
+        Some(Command::Validate) => return cli::validate(&store),
+        Some(Command::ListProfiles) => return cli::list_profiles(&store),
+        Some(Command::Init { force }) => return cli::init(&store, *force),
+        Some(Command::Export { output }) => {
+            return cli::export(&store, cli.selection(), output.as_deref())
+        }
+        Some(Command::Import { input, on_conflict }) => {
+            return cli::import(&store, input, *on_conflict)
+        }
+        Some(Command::TestNotify) => return cli::test_notify(&store),
+        _ => {}
+    }
+
+    // check if config already exists
+    if !store.exists() {
+        store.init()?;
     }
 
-    // get .xivcrafter.json file path
-    let path = home_dir.as_ref().unwrap().join(".xivcrafter.json");
-
-    // check if .xivcrafter.json already exists
-    if !path.exists() {
-        let config = [utils::Config {
-            id: 0,
-            last_used: true,
-            name: String::from(""),
-            amount: 0,
-            food: String::from(""),
-            food_duration: 0,
-            potion: String::from(""),
-            macro1: String::from(""),
-            macro1_duration: 0,
-            macro2: String::from(""),
-            macro2_duration: 0,
-            macro3: String::from(""),
-            macro3_duration: 0,
-            start_pause: String::from(""),
-            stop: String::from(""),
-            confirm: String::from(""),
-            cancel: String::from(""),
-        }];
-
-        let json = serde_json::to_string_pretty(&config)?;
-        let mut file = File::create(&path)?;
-        file.write_all(json.as_bytes())?;
+    if let Some(Command::Run) = &cli.command {
+        return cli::run(&store, cli.selection());
     }
 
+    let mut app = App::init(store, cli.selection())?;
+    app.compact |= cli.compact;
+
     // setup terminal
     enable_raw_mode()?;
     let mut stdout = stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
     // create app and run it
-    let res = run_app(&mut terminal, App::init(path));
+    let res = run_app(&mut terminal, app);
 
     // restore terminal
     disable_raw_mode()?;
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
 
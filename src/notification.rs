@@ -0,0 +1,235 @@
+use crate::config::{Config, Notice, Notifications};
+use crate::crafter::{Kind, Message};
+
+use notify_rust::Notification;
+
+use serde_json::json;
+
+use std::{
+    io::{self, Write},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// how long to wait for a webhook to respond
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// longest to wait on exit for notifications still being delivered
+const EXIT_WAIT: Duration = Duration::from_secs(2);
+
+/// Notifier sends notifications about the crafter's progress
+pub struct Notifier {
+    pub settings: Notifications,
+
+    /// crafts done as of the last message
+    crafted: i32,
+
+    /// whether low stock has been reported this run
+    low_food: bool,
+    low_potion: bool,
+
+    /// notifications still being delivered
+    pending: Vec<JoinHandle<()>>,
+}
+
+impl Notifier {
+    pub fn new(settings: Notifications) -> Notifier {
+        Notifier {
+            settings,
+            crafted: 0,
+            low_food: false,
+            low_potion: false,
+            pending: Vec::new(),
+        }
+    }
+
+    /// message checks a crafter event for anything worth notifying about
+    pub fn message(&mut self, message: &Message, config: &Config) {
+        // a new run counts from 0 again
+        if message.crafted < self.crafted {
+            self.low_food = false;
+            self.low_potion = false;
+        }
+
+        if message.crafted > self.crafted
+            && message.crafted >= config.amount
+            && self.crafted < config.amount
+        {
+            let text = format!("Finished crafting {} x{}", config.name, message.crafted);
+            self.notify(Notice::Complete, &text);
+        }
+        self.crafted = message.crafted;
+
+        if message.kind == Kind::Error {
+            self.notify(Notice::Error, &message.text);
+        }
+
+        if !config.food.is_empty() && config.food_stock > 0 && !self.low_food {
+            let left = config.food_stock - message.food;
+            if left <= self.settings.low_stock {
+                self.low_food = true;
+                let text = format!("{} food left for {}", left.max(0), config.name);
+                self.notify(Notice::LowStock, &text);
+            }
+        }
+
+        if !config.potion.is_empty() && config.potion_stock > 0 && !self.low_potion {
+            let left = config.potion_stock - message.potion;
+            if left <= self.settings.low_stock {
+                self.low_potion = true;
+                let text = format!("{} potions left for {}", left.max(0), config.name);
+                self.notify(Notice::LowStock, &text);
+            }
+        }
+    }
+
+    /// notify sends a notification on every enabled channel, delivering desktop and
+    /// webhook notifications in the background so the crafter is never held up
+    pub fn notify(&mut self, notice: Notice, text: &str) {
+        if !self.settings.events.contains(&notice) {
+            return;
+        }
+
+        if self.settings.bell {
+            bell();
+        }
+
+        if self.settings.desktop || self.settings.webhook.is_some() {
+            let settings = self.settings.clone();
+            let title = notice.to_string();
+            let text = text.to_string();
+
+            self.pending.retain(|handle| !handle.is_finished());
+            self.pending.push(thread::spawn(move || {
+                deliver(&settings, &title, &text);
+            }));
+        }
+    }
+}
+
+impl Drop for Notifier {
+    /// drop gives notifications still being delivered a moment to go out, without
+    /// holding up exit on a slow webhook
+    fn drop(&mut self) {
+        let deadline = Instant::now() + EXIT_WAIT;
+        while self.pending.iter().any(|handle| !handle.is_finished()) && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(50));
+        }
+    }
+}
+
+/// deliver sends a desktop and webhook notification if enabled, returning the errors
+pub fn deliver(settings: &Notifications, title: &str, text: &str) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    if settings.desktop {
+        if let Err(e) = desktop(title, text) {
+            errors.push(format!("desktop: {}", e));
+        }
+    }
+
+    if let Some(url) = &settings.webhook {
+        if let Err(e) = webhook(url, title, text) {
+            errors.push(format!("webhook: {}", e));
+        }
+    }
+
+    errors
+}
+
+/// bell rings the terminal bell
+pub fn bell() {
+    let mut stdout = io::stdout();
+    let _ = stdout.write_all(b"\x07");
+    let _ = stdout.flush();
+}
+
+/// desktop shows a desktop notification
+fn desktop(title: &str, text: &str) -> Result<(), String> {
+    Notification::new()
+        .appname("xivcrafter")
+        .summary(&format!("XIVCrafter: {}", title))
+        .body(text)
+        .show()
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// webhook posts Discord-style JSON to a URL
+fn webhook(url: &str, title: &str, text: &str) -> Result<(), String> {
+    ureq::post(url)
+        .timeout(WEBHOOK_TIMEOUT)
+        .send_json(json!({
+            "username": "XIVCrafter",
+            "content": format!("**{}**: {}", title, text),
+        }))
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::Value;
+
+    use std::{
+        io::{BufRead, BufReader, Read},
+        net::TcpListener,
+    };
+
+    #[test]
+    fn webhook_posts_discord_json() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+
+            let mut request = String::new();
+            reader.read_line(&mut request).unwrap();
+
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+
+            (request, body)
+        });
+
+        let settings = Notifications {
+            webhook: Some(url),
+            ..Notifications::default()
+        };
+        let errors = deliver(&settings, "Complete", "Finished crafting Other x9");
+        assert!(errors.is_empty(), "{:?}", errors);
+
+        let (request, body) = server.join().unwrap();
+        assert!(request.starts_with("POST /hook "), "{}", request);
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body,
+            json!({
+                "username": "XIVCrafter",
+                "content": "**Complete**: Finished crafting Other x9",
+            })
+        );
+    }
+}
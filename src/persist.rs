@@ -0,0 +1,170 @@
+use chrono::{Local, NaiveDateTime};
+
+use std::{
+    cmp::Reverse,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// backups kept per file when settings.json does not set "backups"
+pub const DEFAULT_BACKUPS: usize = 10;
+
+/// timestamp appended to the name of a backup
+const BACKUP_TIME_FORMAT: &str = "%Y%m%dT%H%M%S%3f";
+
+/// Backup is an earlier version of a config file
+#[derive(Clone)]
+pub struct Backup {
+    /// the backup itself
+    pub path: PathBuf,
+    /// the config file it was taken from
+    pub original: PathBuf,
+    /// when the config file was replaced
+    pub time: NaiveDateTime,
+}
+
+/// replace writes a temporary file and renames it over path, so readers never see a
+/// half-written file
+pub fn replace(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+
+    let mut file = File::create(&tmp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&tmp, path)
+}
+
+/// Persist writes config files atomically, keeping backups of what they replace
+///
+/// Backups mirror the layout of the files under root, e.g.
+/// `profiles/tincture.json` is backed up to
+/// `<backups>/profiles/tincture.json.20231018T142501123`.
+#[derive(Clone)]
+pub struct Persist {
+    pub root: PathBuf,
+    pub backups: PathBuf,
+    pub keep: usize,
+}
+
+impl Persist {
+    /// write replaces a file by writing a temporary file and renaming it over the original
+    pub fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+
+        if path.exists() {
+            self.backup(path)?;
+        }
+
+        replace(path, contents)
+    }
+
+    /// backup copies a file into the backups directory, removing its oldest backups
+    pub fn backup(&self, path: &Path) -> io::Result<()> {
+        if self.keep == 0 {
+            return Ok(());
+        }
+
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        let name = match relative.file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => return Ok(()),
+        };
+        let dir = match relative.parent() {
+            Some(parent) => self.backups.join(parent),
+            None => self.backups.clone(),
+        };
+        fs::create_dir_all(&dir)?;
+
+        let time = Local::now().format(BACKUP_TIME_FORMAT);
+        fs::copy(path, dir.join(format!("{}.{}", name, time)))?;
+
+        // remove the oldest backups of this file
+        let prefix = format!("{}.", name);
+        let mut backups: Vec<PathBuf> = fs::read_dir(&dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|backup| {
+                backup.is_file()
+                    && backup
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .and_then(|n| n.strip_prefix(&prefix))
+                        .is_some_and(|time| parse_time(time).is_some())
+            })
+            .collect();
+        backups.sort();
+
+        if backups.len() > self.keep {
+            for backup in &backups[..backups.len() - self.keep] {
+                fs::remove_file(backup)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// list returns every backup, newest first
+    pub fn list(&self) -> io::Result<Vec<Backup>> {
+        let mut backups = Vec::new();
+        if self.backups.exists() {
+            self.collect(&self.backups, &mut backups)?;
+        }
+        backups.sort_by_key(|backup| Reverse(backup.time));
+
+        Ok(backups)
+    }
+
+    /// restore replaces the original file with a backup, backing up the current version first
+    pub fn restore(&self, backup: &Backup) -> io::Result<()> {
+        let contents = fs::read(&backup.path)?;
+        self.write(&backup.original, &contents)
+    }
+
+    /// collect adds the backups in a directory and its subdirectories
+    fn collect(&self, dir: &Path, backups: &mut Vec<Backup>) -> io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                self.collect(&path, backups)?;
+                continue;
+            }
+
+            let name = match path.file_name().and_then(|n| n.to_str()) {
+                Some(name) => name,
+                None => continue,
+            };
+            let (original, time) = match name.rsplit_once('.') {
+                Some((original, time)) => match parse_time(time) {
+                    Some(time) => (original.to_string(), time),
+                    None => continue,
+                },
+                None => continue,
+            };
+
+            let relative = path
+                .parent()
+                .and_then(|parent| parent.strip_prefix(&self.backups).ok())
+                .unwrap_or(Path::new(""));
+            backups.push(Backup {
+                original: self.root.join(relative).join(original),
+                path,
+                time,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// parse_time reads the timestamp of a backup
+fn parse_time(time: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(time, BACKUP_TIME_FORMAT).ok()
+}
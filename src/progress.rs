@@ -0,0 +1,153 @@
+use crate::config::Config;
+use crate::crafter::{self, Kind, Message, POTION_DURATION};
+
+use chrono::{DateTime, Local};
+
+use serde::Serialize;
+
+/// Status is the crafter's progress as reported to the status command
+#[derive(Serialize)]
+pub struct Status {
+    pub profile: String,
+    pub state: &'static str,
+    pub crafted: i32,
+    pub amount: i32,
+    pub food: i32,
+    pub potion: i32,
+    /// seconds left on the food and potion buffs, None when not active
+    pub food_left: Option<i64>,
+    pub potion_left: Option<i64>,
+    /// seconds until the run finishes and when, as RFC 3339, None when too far off to
+    /// estimate
+    pub remaining: Option<i64>,
+    pub finish: Option<String>,
+}
+
+/// buff_left returns the seconds left on a buff started at start_time, None when not active
+pub fn buff_left(start_time: i64, duration: i64, now: i64) -> Option<i64> {
+    (start_time > 0).then(|| (start_time + duration - now).max(0))
+}
+
+/// Estimate projects the rest of the run from the profile and the observed crafts
+pub struct Estimate {
+    /// average seconds per craft
+    pub per_craft: f64,
+    /// number of crafts the average was observed from, 0 when planned from the config
+    pub observed: usize,
+    /// seconds until the last craft finishes
+    pub remaining: i64,
+    pub finish: DateTime<Local>,
+    /// food and potions still to be consumed, if used
+    pub food: Option<i64>,
+    pub potion: Option<i64>,
+}
+
+/// Progress is a run's progress as taken from the crafter's events, so the TUI and
+/// headless runs report it the same way
+#[derive(Clone, Default)]
+pub struct Progress {
+    pub crafted: i32,
+    pub food: i32,
+    pub potion: i32,
+
+    // Buffs
+    pub food_start_time: i64,
+    pub potion_start_time: i64,
+
+    // Statistics
+    pub craft_start: Option<DateTime<Local>>,
+    pub craft_times: Vec<f64>,
+}
+
+impl Progress {
+    /// record takes the progress from a crafter event
+    pub fn record(&mut self, message: &Message) {
+        // time each craft from its first event until it is counted
+        if message.crafted > self.crafted {
+            if let Some(start) = self.craft_start.take() {
+                let secs = (message.time - start).num_milliseconds() as f64 / 1000.0;
+                self.craft_times.push(secs);
+            }
+        } else if message.crafted < self.crafted {
+            self.craft_start = None;
+        } else if message.kind == Kind::Craft && self.craft_start.is_none() {
+            self.craft_start = Some(message.time);
+        }
+
+        self.crafted = message.crafted;
+        self.food = message.food;
+        self.potion = message.potion;
+        self.food_start_time = message.food_start_time;
+        self.potion_start_time = message.potion_start_time;
+    }
+
+    /// estimate projects the time and consumables needed for the rest of the run, None
+    /// when the run is too long to estimate
+    pub fn estimate(&self, profile: &Config) -> Option<Estimate> {
+        let observed = self.craft_times.len();
+        let per_craft = if observed > 0 {
+            self.craft_times.iter().sum::<f64>() / observed as f64
+        } else {
+            crafter::craft_duration(profile)?.as_secs_f64()
+        };
+
+        let now = Local::now();
+        let left = (profile.amount - self.crafted).max(0);
+        let mut remaining = (per_craft * left as f64) as i64;
+        if let Some(start) = self.craft_start {
+            remaining = (remaining - (now - start).num_seconds()).max(0);
+        }
+        let finish = now.checked_add_signed(chrono::Duration::try_seconds(remaining)?)?;
+
+        // buffs are refreshed once they run out before the last craft
+        let refreshes = |start_time: i64, duration: i64| {
+            let active = if start_time > 0 {
+                start_time
+                    .saturating_add(duration)
+                    .saturating_sub(now.timestamp())
+                    .max(0)
+            } else {
+                0
+            };
+            if remaining <= active || left == 0 {
+                0
+            } else if duration > 0 {
+                (remaining - active).saturating_add(duration - 1) / duration
+            } else {
+                left as i64
+            }
+        };
+        let food = (!profile.food.is_empty())
+            .then(|| refreshes(self.food_start_time, profile.food_duration));
+        let potion = (!profile.potion.is_empty())
+            .then(|| refreshes(self.potion_start_time, POTION_DURATION));
+
+        Some(Estimate {
+            per_craft,
+            observed,
+            remaining,
+            finish,
+            food,
+            potion,
+        })
+    }
+
+    /// status reports the progress for the status command and status file
+    pub fn status(&self, profile: &Config, state: &'static str) -> Status {
+        let now = Local::now().timestamp();
+        let estimate = self.estimate(profile);
+
+        Status {
+            profile: profile.name.clone(),
+            state,
+            crafted: self.crafted,
+            amount: profile.amount,
+            food: self.food,
+            potion: self.potion,
+            food_left: buff_left(self.food_start_time, profile.food_duration, now),
+            potion_left: buff_left(self.potion_start_time, POTION_DURATION, now),
+            remaining: estimate.as_ref().map(|estimate| estimate.remaining),
+            finish: estimate.map(|estimate| estimate.finish.to_rfc3339()),
+        }
+    }
+}
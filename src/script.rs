@@ -0,0 +1,170 @@
+use crate::app::TICK_RATE;
+use crate::crafter::{Crafter, Kind, Message, POTION_DURATION};
+use crate::progress;
+
+use chrono::Local;
+
+use rhai::{Engine, EvalAltResult, Scope, AST};
+
+use std::{
+    cell::RefCell,
+    path::Path,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Script is a profile's craft loop written in Rhai, run once per craft in place of the
+/// built-in start, food, potion and macro order
+///
+/// Scripts can call:
+/// - `press(key)` to press a key
+/// - `wait(seconds)` to wait
+/// - `start_craft()` and `stop_craft()` to open and close the synthesis window
+/// - `run_macro(n)` to activate macro n and wait for it to finish
+/// - `check_food()` and `check_potion()` to renew a buff that has run out
+/// - `food_left()` and `potion_left()` for the seconds left on a buff
+/// - `print(text)` to add to the activity log
+///
+/// and read `crafted`, `amount`, `food` and `potion`, the counts before this craft.
+///
+/// Stopping the crafter ends a script part way through, even one stuck in a loop or a
+/// long wait.
+pub struct Script {
+    ast: AST,
+}
+
+impl Script {
+    /// load reads and compiles a script
+    pub fn load(path: &Path) -> Result<Script, String> {
+        let ast = Engine::new()
+            .compile_file(path.to_path_buf())
+            .map_err(|e| e.to_string())?;
+
+        Ok(Script { ast })
+    }
+
+    /// craft runs the script for one craft, until it ends or program_signal is cleared
+    pub fn craft(
+        &self,
+        crafter: &mut Crafter,
+        tx: &mpsc::Sender<Message>,
+        program_signal: &Arc<AtomicBool>,
+    ) -> Result<(), String> {
+        let mut scope = Scope::new();
+        scope.push_constant("crafted", crafter.current_amount as i64);
+        scope.push_constant("amount", crafter.config.amount as i64);
+        scope.push_constant("food", crafter.food_count as i64);
+        scope.push_constant("potion", crafter.potion_count as i64);
+
+        let shared = Rc::new(RefCell::new(crafter.clone()));
+        let result = engine(&shared, tx, program_signal)
+            .run_ast_with_scope(&mut scope, &self.ast)
+            .map_err(|e| e.to_string());
+
+        *crafter = shared.borrow().clone();
+        result
+    }
+}
+
+/// engine creates an engine whose functions act on the crafter
+fn engine(
+    crafter: &Rc<RefCell<Crafter>>,
+    tx: &mpsc::Sender<Message>,
+    program_signal: &Arc<AtomicBool>,
+) -> Engine {
+    let mut engine = Engine::new();
+
+    let signal = program_signal.clone();
+    engine.on_progress(move |_| {
+        if signal.load(Ordering::Relaxed) {
+            None
+        } else {
+            Some("stopped".into())
+        }
+    });
+
+    let c = crafter.clone();
+    engine.register_fn(
+        "press",
+        move |key: &str| -> Result<(), Box<EvalAltResult>> { Ok(c.borrow().press(key)?) },
+    );
+
+    let signal = program_signal.clone();
+    engine.register_fn("wait", move |seconds: i64| wait(&signal, seconds as f64));
+    let signal = program_signal.clone();
+    engine.register_fn("wait", move |seconds: f64| wait(&signal, seconds));
+
+    let (c, t) = (crafter.clone(), tx.clone());
+    engine.register_fn("start_craft", move || c.borrow_mut().start_craft(&t));
+    let (c, t) = (crafter.clone(), tx.clone());
+    engine.register_fn("stop_craft", move || c.borrow_mut().stop_craft(&t));
+
+    let (c, t) = (crafter.clone(), tx.clone());
+    engine.register_fn(
+        "run_macro",
+        move |number: i64| -> Result<(), Box<EvalAltResult>> {
+            Ok(c.borrow().activate_macro(&t, number as usize)?)
+        },
+    );
+
+    let (c, t) = (crafter.clone(), tx.clone());
+    engine.register_fn("check_food", move || {
+        let mut crafter = c.borrow_mut();
+        if !crafter.config.food.is_empty() {
+            crafter.send(&t, Kind::Food, "Checking food...");
+            crafter.check_food(&t);
+        }
+    });
+    let (c, t) = (crafter.clone(), tx.clone());
+    engine.register_fn("check_potion", move || {
+        let mut crafter = c.borrow_mut();
+        if !crafter.config.potion.is_empty() {
+            crafter.send(&t, Kind::Potion, "Checking potion...");
+            crafter.check_potion(&t);
+        }
+    });
+
+    let c = crafter.clone();
+    engine.register_fn("food_left", move || {
+        let crafter = c.borrow();
+        let now = Local::now().timestamp();
+        progress::buff_left(crafter.food_start_time, crafter.config.food_duration, now).unwrap_or(0)
+    });
+    let c = crafter.clone();
+    engine.register_fn("potion_left", move || {
+        let crafter = c.borrow();
+        let now = Local::now().timestamp();
+        progress::buff_left(crafter.potion_start_time, POTION_DURATION, now).unwrap_or(0)
+    });
+
+    // print goes to the activity log, since stdout belongs to the TUI
+    let (c, t) = (crafter.clone(), tx.clone());
+    engine.on_print(move |text| c.borrow().send(&t, Kind::Craft, text));
+    let (c, t) = (crafter.clone(), tx.clone());
+    engine.on_debug(move |text, _, _| c.borrow().send(&t, Kind::Craft, text));
+
+    engine
+}
+
+/// wait sleeps a tick at a time, ending early when the crafter is stopped
+fn wait(program_signal: &AtomicBool, seconds: f64) -> Result<(), Box<EvalAltResult>> {
+    let end = Duration::try_from_secs_f64(seconds)
+        .ok()
+        .and_then(|wait| Instant::now().checked_add(wait))
+        .ok_or_else(|| format!("cannot wait {} seconds", seconds))?;
+    loop {
+        if !program_signal.load(Ordering::Relaxed) {
+            return Err("stopped".into());
+        }
+        let left = end.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Ok(());
+        }
+        thread::sleep(left.min(TICK_RATE));
+    }
+}
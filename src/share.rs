@@ -0,0 +1,64 @@
+use crate::config::Profile;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+
+use std::{fs, io, path::Path};
+
+/// prefix of every share string
+const PREFIX: &str = "xivcrafter:";
+
+/// encode turns a profile into a share string that can be pasted into chat
+pub fn encode(profile: &Profile) -> io::Result<String> {
+    let json = serde_json::to_string(profile)?;
+    Ok(format!("{}{}", PREFIX, URL_SAFE_NO_PAD.encode(json)))
+}
+
+/// decode reads a profile from a share string
+pub fn decode(share: &str) -> io::Result<Profile> {
+    let encoded = share.trim().strip_prefix(PREFIX).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "not an xivcrafter share string",
+        )
+    })?;
+    let json = URL_SAFE_NO_PAD
+        .decode(encoded.trim())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    Ok(serde_json::from_slice(&json)?)
+}
+
+/// read reads a profile from a share string, a profile file or a pasted JSON profile
+pub fn read(input: &str) -> io::Result<Profile> {
+    let input = input.trim();
+    if input.starts_with(PREFIX) {
+        return decode(input);
+    }
+
+    let json = if Path::new(input).is_file() {
+        fs::read_to_string(input)?
+    } else {
+        input.to_string()
+    };
+
+    // accept a single profile or a legacy file holding one
+    let value: serde_json::Value = serde_json::from_str(&json)?;
+    let value = match value {
+        serde_json::Value::Array(mut profiles) if profiles.len() == 1 => profiles.remove(0),
+        serde_json::Value::Array(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "expected a single profile",
+            ))
+        }
+        value => value,
+    };
+
+    Ok(serde_json::from_value(value)?)
+}
+
+/// write writes a profile to a standalone profile file
+pub fn write(profile: &Profile, path: &Path) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(profile)?;
+    fs::write(path, json)
+}
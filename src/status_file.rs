@@ -0,0 +1,99 @@
+use crate::config::StatusFile;
+use crate::persist;
+use crate::progress::Status;
+
+use std::{
+    fs,
+    time::{Duration, Instant},
+};
+
+/// Exporter writes the crafter's status to files for status bars, as JSON and as a
+/// line of text like `Crafting 123/400 · food 12m`
+pub struct Exporter {
+    settings: StatusFile,
+
+    /// text and counts of the last status written, to skip writes when nothing changed
+    written: Option<(String, i32, i32)>,
+    last_write: Option<Instant>,
+}
+
+impl Exporter {
+    pub fn new(settings: StatusFile) -> Exporter {
+        Exporter {
+            settings,
+            written: None,
+            last_write: None,
+        }
+    }
+
+    /// update writes the status if it changed, at most once per interval
+    ///
+    /// A change held back by the interval is written by a later update once the
+    /// interval has passed.
+    pub fn update(&mut self, status: &Status) {
+        let line = line(status);
+        let key = (line, status.food, status.potion);
+        if self.written.as_ref() == Some(&key) {
+            return;
+        }
+
+        let interval = Duration::from_secs(self.settings.interval);
+        if self
+            .last_write
+            .is_some_and(|last| last.elapsed() < interval)
+        {
+            return;
+        }
+
+        // a failed write is retried on the next update
+        if self.write(status, &key.0) {
+            self.written = Some(key);
+        }
+        self.last_write = Some(Instant::now());
+    }
+
+    fn write(&self, status: &Status, line: &str) -> bool {
+        let mut ok = true;
+
+        if let Some(path) = &self.settings.json {
+            let json = serde_json::to_string(status).unwrap();
+            ok &= persist::replace(path, json.as_bytes()).is_ok();
+        }
+
+        if let Some(path) = &self.settings.text {
+            ok &= persist::replace(path, format!("{}\n", line).as_bytes()).is_ok();
+        }
+
+        ok
+    }
+}
+
+impl Drop for Exporter {
+    /// drop removes the status files, so a missing file means XIVCrafter is not running
+    fn drop(&mut self) {
+        for path in [&self.settings.json, &self.settings.text]
+            .into_iter()
+            .flatten()
+        {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// line formats the status as one line of text
+fn line(status: &Status) -> String {
+    let state = match status.state {
+        "crafting" => "Crafting",
+        "paused" => "Paused",
+        _ => "Stopped",
+    };
+    let mut line = format!("{} {}/{}", state, status.crafted, status.amount);
+
+    for (name, left) in [("food", status.food_left), ("potion", status.potion_left)] {
+        if let Some(left) = left {
+            line.push_str(&format!(" · {} {}m", name, (left + 59) / 60));
+        }
+    }
+
+    line
+}
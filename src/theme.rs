@@ -0,0 +1,186 @@
+use crate::crafter::Kind;
+
+use serde::{Deserialize, Serialize};
+
+use tui::style::{Color, Modifier, Style};
+
+/// ThemeName selects a theme with "theme" in settings.json
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeName {
+    #[default]
+    Default,
+    HighContrast,
+    Monochrome,
+}
+
+/// Theme is the set of styles used to draw the TUI
+#[derive(Clone)]
+pub struct Theme {
+    // Text
+    pub text: Style,
+    pub hint: Style,
+    pub muted: Style,
+    pub tab: Style,
+    pub highlight: Style,
+    pub status: Style,
+    pub error: Style,
+    pub button: Style,
+
+    // Crafter States
+    pub crafting: Style,
+    pub paused: Style,
+    pub stopped: Style,
+
+    // Gauges
+    pub progress: Style,
+    pub chain: Style,
+    pub buff_high: Style,
+    pub buff_mid: Style,
+    pub buff_low: Style,
+
+    // Activity Log
+    pub log_status: Style,
+    pub log_countdown: Style,
+    pub log_craft: Style,
+    pub log_macro: Style,
+    pub log_food: Style,
+    pub log_potion: Style,
+    pub log_hook: Style,
+    pub log_error: Style,
+
+    // Config Syntax
+    pub key: Style,
+    pub string: Style,
+    pub number: Style,
+    pub literal: Style,
+}
+
+impl Theme {
+    pub fn new(name: ThemeName) -> Theme {
+        let fg = |color| Style::default().fg(color);
+        let bold = Modifier::BOLD;
+
+        match name {
+            ThemeName::Default => Theme {
+                text: fg(Color::White),
+                hint: fg(Color::Gray),
+                muted: fg(Color::DarkGray),
+                tab: fg(Color::Green),
+                highlight: fg(Color::Yellow).add_modifier(bold),
+                status: fg(Color::Yellow),
+                error: fg(Color::Red),
+                button: fg(Color::Black).bg(Color::Gray).add_modifier(bold),
+
+                crafting: fg(Color::Green),
+                paused: fg(Color::Red),
+                stopped: Style::default(),
+
+                progress: fg(Color::LightBlue).add_modifier(Modifier::ITALIC | bold),
+                chain: fg(Color::Blue),
+                buff_high: fg(Color::Green),
+                buff_mid: fg(Color::Yellow),
+                buff_low: fg(Color::Red),
+
+                log_status: fg(Color::Gray),
+                log_countdown: fg(Color::Yellow),
+                log_craft: fg(Color::Green),
+                log_macro: fg(Color::LightBlue),
+                log_food: fg(Color::Magenta),
+                log_potion: fg(Color::Cyan),
+                log_hook: fg(Color::Blue),
+                log_error: fg(Color::Red),
+
+                key: fg(Color::Cyan),
+                string: fg(Color::Green),
+                number: fg(Color::Magenta),
+                literal: fg(Color::Yellow),
+            },
+            ThemeName::HighContrast => Theme {
+                text: fg(Color::White),
+                hint: fg(Color::White),
+                muted: fg(Color::Gray),
+                tab: fg(Color::White).add_modifier(bold),
+                highlight: fg(Color::Black).bg(Color::Yellow).add_modifier(bold),
+                status: fg(Color::LightYellow).add_modifier(bold),
+                error: fg(Color::LightRed).add_modifier(bold),
+                button: fg(Color::Black).bg(Color::White).add_modifier(bold),
+
+                crafting: fg(Color::LightGreen).add_modifier(bold),
+                paused: fg(Color::LightYellow).add_modifier(bold),
+                stopped: fg(Color::White).add_modifier(bold),
+
+                progress: fg(Color::LightCyan).add_modifier(bold),
+                chain: fg(Color::White),
+                buff_high: fg(Color::LightGreen),
+                buff_mid: fg(Color::LightYellow),
+                buff_low: fg(Color::LightRed),
+
+                log_status: fg(Color::White),
+                log_countdown: fg(Color::LightYellow),
+                log_craft: fg(Color::LightGreen),
+                log_macro: fg(Color::LightCyan),
+                log_food: fg(Color::LightMagenta),
+                log_potion: fg(Color::LightBlue),
+                log_hook: fg(Color::White).add_modifier(Modifier::ITALIC),
+                log_error: fg(Color::LightRed).add_modifier(bold),
+
+                key: fg(Color::LightCyan),
+                string: fg(Color::LightGreen),
+                number: fg(Color::LightMagenta),
+                literal: fg(Color::LightYellow),
+            },
+            ThemeName::Monochrome => {
+                let plain = Style::default();
+                Theme {
+                    text: plain,
+                    hint: plain,
+                    muted: plain.add_modifier(Modifier::DIM),
+                    tab: plain,
+                    highlight: plain.add_modifier(Modifier::REVERSED | bold),
+                    status: plain.add_modifier(bold),
+                    error: plain.add_modifier(bold | Modifier::UNDERLINED),
+                    button: plain.add_modifier(Modifier::REVERSED),
+
+                    crafting: plain.add_modifier(bold),
+                    paused: plain.add_modifier(bold | Modifier::UNDERLINED),
+                    stopped: plain,
+
+                    progress: fg(Color::White).bg(Color::Black),
+                    chain: fg(Color::White).bg(Color::Black),
+                    buff_high: fg(Color::White).bg(Color::Black),
+                    buff_mid: fg(Color::White).bg(Color::Black),
+                    buff_low: fg(Color::White).bg(Color::Black),
+
+                    log_status: plain,
+                    log_countdown: plain,
+                    log_craft: plain.add_modifier(bold),
+                    log_macro: plain,
+                    log_food: plain,
+                    log_potion: plain,
+                    log_hook: plain.add_modifier(Modifier::ITALIC),
+                    log_error: plain.add_modifier(bold),
+
+                    key: plain.add_modifier(bold),
+                    string: plain,
+                    number: plain,
+                    literal: plain.add_modifier(Modifier::ITALIC),
+                }
+            }
+        }
+    }
+
+    /// log returns the style of a type of crafter event
+    pub fn log(&self, kind: Kind) -> Style {
+        match kind {
+            Kind::Status => self.log_status,
+            Kind::Countdown => self.log_countdown,
+            Kind::Craft => self.log_craft,
+            Kind::Macro => self.log_macro,
+            Kind::Food => self.log_food,
+            Kind::Potion => self.log_potion,
+            Kind::Hook => self.log_hook,
+            Kind::Error => self.log_error,
+        }
+    }
+}
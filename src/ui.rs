@@ -1,30 +1,70 @@
-use crate::app::App;
+use crate::app::{App, Quit};
+use crate::config::Mode;
+use crate::crafter::POTION_DURATION;
+use crate::progress::Estimate;
+use crate::theme::Theme;
+use crate::view::{Line, Token};
 
-use std::{
-    fs,
-    sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
-    },
+use chrono::{Local, TimeZone};
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
 };
 
 use tui::{
     backend::Backend,
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Span, Spans},
-    widgets::{Block, Borders, Gauge, Paragraph, Row, Table, Tabs, Wrap},
+    widgets::{
+        Block, Borders, Cell, Clear, Gauge, List, ListItem, ListState, Paragraph, Row, Table, Tabs,
+        Wrap,
+    },
     Frame,
 };
 
+/// Areas are the clickable parts of the last drawn frame
+#[derive(Default)]
+pub struct Areas {
+    pub tabs: Vec<Rect>,
+    pub start_pause: Rect,
+    pub stop: Rect,
+    pub log: Rect,
+    pub config: Rect,
+}
+
+/// hit checks whether a mouse position is inside an area
+pub fn hit(area: Rect, column: u16, row: u16) -> bool {
+    column >= area.x && column < area.x + area.width && row >= area.y && row < area.y + area.height
+}
+
+/// buffs at or below this percent are shown as running out
+const LOW_BUFF: u16 = 20;
+
+/// terminals narrower than this show the Home tab in a single column
+const NARROW_WIDTH: u16 = 100;
+
+/// terminals smaller than this always use the compact view
+const COMPACT_WIDTH: u16 = 60;
+const COMPACT_HEIGHT: u16 = 20;
+
 pub fn ui<B: Backend>(
     f: &mut Frame<B>,
     app: &App,
     message: &str,
     program_signal: &Arc<AtomicBool>,
     crafter_signal: &Arc<AtomicBool>,
+    areas: &mut Areas,
 ) {
     let size = f.size();
+    *areas = Areas::default();
+
+    if app.compact || size.width < COMPACT_WIDTH || size.height < COMPACT_HEIGHT {
+        ui_compact(f, app, message, program_signal, crafter_signal, size);
+        draw_overlays(f, app, size);
+        return;
+    }
 
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -42,25 +82,44 @@ pub fn ui<B: Backend>(
     let tabs = app
         .tabs
         .iter()
-        .map(|t| Spans::from(vec![Span::styled(*t, Style::default().fg(Color::Green))]))
+        .map(|t| Spans::from(vec![Span::styled(*t, app.theme.tab)]))
         .collect();
     let tabs_content = Tabs::new(tabs)
         .block(Block::default().borders(Borders::ALL).title("XIVCrafter"))
         .select(app.index)
-        .highlight_style(
-            Style::default()
-                .add_modifier(Modifier::BOLD)
-                .fg(Color::Yellow),
-        );
+        .highlight_style(app.theme.highlight);
     f.render_widget(tabs_content, chunks[0]);
 
+    // each title is padded by a space on both sides and followed by a divider
+    let mut x = chunks[0].x + 1;
+    for tab in &app.tabs {
+        let width = tab.chars().count() as u16 + 2;
+        areas.tabs.push(Rect::new(x, chunks[0].y + 1, width, 1));
+        x += width + 1;
+    }
+
     // Footer
     let footer = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
         .split(chunks[2]);
-    let text = String::from("< > to switch tabs, q to quit");
-    f.render_widget(Paragraph::new(text), footer[0]);
+    match &app.error {
+        Some(error) => {
+            let text = format!("Config error: {}", error);
+            f.render_widget(Paragraph::new(text).style(app.theme.error), footer[0]);
+        }
+        None => {
+            let keys = &app.keys;
+            let text = format!(
+                "{}/{} to switch tabs, {} for help, {} to quit",
+                key_label(&keys.previous_tab),
+                key_label(&keys.next_tab),
+                key_label(&keys.help),
+                key_label(&keys.quit)
+            );
+            f.render_widget(Paragraph::new(text), footer[0]);
+        }
+    }
     f.render_widget(
         Paragraph::new(String::from("github.com/kn-lim/xivcrafter-rs"))
             .alignment(tui::layout::Alignment::Right),
@@ -68,10 +127,296 @@ pub fn ui<B: Backend>(
     );
 
     match app.index {
-        0 => ui_home(f, app, message, program_signal, crafter_signal, chunks[1]),
-        1 => ui_config(f, app, chunks[1]),
+        0 => ui_home(
+            f,
+            app,
+            message,
+            program_signal,
+            crafter_signal,
+            areas,
+            chunks[1],
+        ),
+        1 => ui_profiles(f, app, chunks[1]),
+        2 => ui_config(f, app, areas, chunks[1]),
         _ => {}
     };
+
+    draw_overlays(f, app, size);
+}
+
+/// draw_overlays draws the help and quit dialogs over the current view
+fn draw_overlays<B>(f: &mut Frame<B>, app: &App, size: Rect)
+where
+    B: Backend,
+{
+    if app.help {
+        draw_help(f, app, size);
+    }
+
+    if let Some(quit) = app.quit {
+        draw_quit(f, &app.theme, quit, size);
+    }
+}
+
+/// state returns the name and style of the crafter's state
+fn state(
+    theme: &Theme,
+    program_signal: &Arc<AtomicBool>,
+    crafter_signal: &Arc<AtomicBool>,
+) -> (&'static str, Style) {
+    // each state has its own symbol so it can be told apart without colour
+    if !program_signal.load(Ordering::Relaxed) {
+        ("■ STOPPED", theme.stopped)
+    } else if crafter_signal.load(Ordering::Relaxed) {
+        ("▶ CRAFTING", theme.crafting)
+    } else {
+        ("‖ PAUSED", theme.paused)
+    }
+}
+
+// Compact View
+fn ui_compact<B>(
+    f: &mut Frame<B>,
+    app: &App,
+    message: &str,
+    program_signal: &Arc<AtomicBool>,
+    crafter_signal: &Arc<AtomicBool>,
+    area: Rect,
+) where
+    B: Backend,
+{
+    let (name, style) = state(&app.theme, program_signal, crafter_signal);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().add_modifier(Modifier::BOLD))
+        .title(format!("{}: {}", name, app.profile.name))
+        .style(style);
+    f.render_widget(block, area);
+
+    let buffs = buffs(app);
+    let mut constraints = vec![
+        Constraint::Length(1), // Messages
+        Constraint::Length(1), // Progress Gauge
+        Constraint::Length(1), // Estimate
+    ];
+    constraints.extend(vec![Constraint::Length(1); buffs.len()]);
+    constraints.push(Constraint::Min(0));
+    constraints.push(Constraint::Length(1)); // Instructions
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints(constraints)
+        .split(area);
+
+    f.render_widget(Paragraph::new(message), rows[0]);
+
+    let gauge = Gauge::default()
+        .gauge_style(app.theme.progress)
+        .percent(progress(app))
+        .label(format!("{}/{}", app.progress.crafted, app.profile.amount));
+    f.render_widget(gauge, rows[1]);
+
+    let eta = match app.estimate() {
+        Some(estimate) => format!(
+            "ETA {} ({}), {:.1}s per craft",
+            format_secs(estimate.remaining),
+            estimate.finish.format("%H:%M:%S"),
+            estimate.per_craft
+        ),
+        None => String::from("ETA unknown, the run is too long to estimate"),
+    };
+    f.render_widget(Paragraph::new(eta).style(app.theme.hint), rows[2]);
+
+    let now = Local::now().timestamp();
+    for (i, buff) in buffs.iter().enumerate() {
+        let (label, percent, style) = match buff_timer(&app.theme, buff, now) {
+            Some((remaining, _, percent, style)) => (
+                format!(
+                    "{} {}{}",
+                    buff.name,
+                    format_secs(remaining),
+                    low_buff(percent)
+                ),
+                percent,
+                style,
+            ),
+            None => (format!("{} not active", buff.name), 0, app.theme.muted),
+        };
+        let gauge = Gauge::default()
+            .gauge_style(style)
+            .percent(percent)
+            .label(label);
+        f.render_widget(gauge, rows[3 + i]);
+    }
+
+    let keys = &app.keys;
+    let instructions = format!(
+        "{} start/pause, {} stop, {} full view, {} quit",
+        app.profile.start_pause,
+        app.profile.stop,
+        key_label(&keys.compact),
+        key_label(&keys.quit)
+    );
+    f.render_widget(
+        Paragraph::new(instructions).style(app.theme.muted),
+        rows[rows.len() - 1],
+    );
+}
+
+/// centered returns an area of the given size in the middle of the frame
+fn centered(width: u16, height: u16, size: Rect) -> Rect {
+    let width = width.min(size.width);
+    let height = height.min(size.height);
+    Rect::new(
+        size.x + (size.width - width) / 2,
+        size.y + (size.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+/// draw_quit asks how to quit while the crafter is running
+fn draw_quit<B>(f: &mut Frame<B>, theme: &Theme, quit: Quit, size: Rect)
+where
+    B: Backend,
+{
+    let key = |key: &'static str, text: &'static str| {
+        Spans::from(vec![
+            Span::styled(format!("{:<5}", key), theme.highlight),
+            Span::raw(text),
+        ])
+    };
+
+    let text = match quit {
+        Quit::Confirm => vec![
+            Spans::from("The crafter is still running."),
+            Spans::from(""),
+            key("1", "Quit now"),
+            key("2", "Finish the current craft, then quit"),
+            key(
+                "3",
+                "Finish the current craft, close the crafting log, then quit",
+            ),
+            key("Esc", "Cancel"),
+        ],
+        Quit::AfterCraft => vec![
+            Spans::from("Quitting after the current craft..."),
+            Spans::from(""),
+            key("Esc", "Cancel"),
+        ],
+        Quit::AfterClose => vec![
+            Spans::from("Closing the crafting log before quitting..."),
+            Spans::from(""),
+            key("Esc", "Cancel"),
+        ],
+    };
+
+    let area = centered(70, text.len() as u16 + 2, size);
+    let dialog = Paragraph::new(text).block(
+        Block::default()
+            .title("Quit")
+            .borders(Borders::ALL)
+            .border_style(theme.error),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(dialog, area);
+}
+
+/// key_label returns a key's name as shown in the TUI
+fn key_label(key: &str) -> String {
+    match key.to_lowercase().as_str() {
+        "pageup" => String::from("PageUp"),
+        "pagedown" => String::from("PageDown"),
+        _ if key.chars().count() == 1 => key.to_string(),
+        _ => {
+            let mut chars = key.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::from("(none)"),
+            }
+        }
+    }
+}
+
+/// draw_help shows every active keybinding over the current tab
+fn draw_help<B>(f: &mut Frame<B>, app: &App, size: Rect)
+where
+    B: Backend,
+{
+    let keys = &app.keys;
+    let profile = &app.profile;
+    let sections = [
+        (
+            "Global",
+            vec![
+                (&profile.start_pause, "Start/pause crafting (profile)"),
+                (&profile.stop, "Stop crafting (profile)"),
+                (&keys.next_tab, "Next tab"),
+                (&keys.previous_tab, "Previous tab"),
+                (&keys.help, "Show this help"),
+                (&keys.compact, "Toggle the compact view"),
+                (&keys.quit, "Quit"),
+            ],
+        ),
+        (
+            "Home",
+            vec![
+                (&keys.up, "Scroll the activity log up"),
+                (&keys.down, "Scroll the activity log down"),
+                (&keys.latest, "Jump to the newest event"),
+                (&keys.filter, "Filter events by type"),
+            ],
+        ),
+        (
+            "Profiles",
+            vec![
+                (&keys.up, "Select the previous profile"),
+                (&keys.down, "Select the next profile"),
+                (&keys.select, "Use the selected profile"),
+                (&keys.export, "Export as a share string"),
+                (&keys.export_file, "Export to a file"),
+                (&keys.import, "Import a share string or file"),
+            ],
+        ),
+        (
+            "Config",
+            vec![
+                (&keys.up, "Select the previous backup"),
+                (&keys.down, "Select the next backup"),
+                (&keys.restore, "Restore the selected backup"),
+                (&keys.page_up, "Scroll the config up"),
+                (&keys.page_down, "Scroll the config down"),
+            ],
+        ),
+    ];
+
+    let mut rows = Vec::new();
+    for (section, bindings) in sections {
+        if !rows.is_empty() {
+            rows.push(Row::new(vec![Cell::from("")]));
+        }
+        rows.push(Row::new(vec![
+            Cell::from(section).style(app.theme.highlight)
+        ]));
+        for (key, action) in bindings {
+            rows.push(Row::new(vec![
+                Cell::from(key_label(key)),
+                Cell::from(action),
+            ]));
+        }
+    }
+
+    let area = centered(56, rows.len() as u16 + 2, size);
+
+    let table = Table::new(rows)
+        .block(
+            Block::default()
+                .title("Keybindings (press any key to close)")
+                .borders(Borders::ALL),
+        )
+        .widths(&[Constraint::Length(12), Constraint::Length(40)]);
+    f.render_widget(Clear, area);
+    f.render_widget(table, area);
 }
 
 // Home Tab
@@ -81,18 +426,67 @@ pub fn ui_home<B>(
     message: &str,
     program_signal: &Arc<AtomicBool>,
     crafter_signal: &Arc<AtomicBool>,
+    areas: &mut Areas,
     area: Rect,
 ) where
     B: Backend,
 {
-    let chunks = Layout::default()
-        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
-        .direction(Direction::Horizontal)
-        .split(area);
+    // narrow terminals leave out the settings to keep the status readable
+    let side = if area.width < NARROW_WIDTH {
+        area
+    } else {
+        let chunks = Layout::default()
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+            .direction(Direction::Horizontal)
+            .split(area);
+        draw_settings(f, app, chunks[0]);
+        chunks[1]
+    };
 
-    draw_settings(f, app, chunks[0]);
+    // short terminals leave out the buffs and then the macro progress
+    let buffs = buffs(app);
+    let mut buff_rows = buff_height(buffs.len());
+    let mut macro_rows = 6;
+    if side.height < 13 + macro_rows + buff_rows {
+        buff_rows = 0;
+    }
+    if side.height < 13 + macro_rows {
+        macro_rows = 0;
+    }
 
-    draw_status(f, app, message, program_signal, crafter_signal, chunks[1]);
+    let side = Layout::default()
+        .constraints(
+            [
+                Constraint::Length(13),         // Status
+                Constraint::Length(macro_rows), // Macro
+                Constraint::Length(buff_rows),  // Buffs
+                Constraint::Min(0),             // Activity Log
+            ]
+            .as_ref(),
+        )
+        .direction(Direction::Vertical)
+        .split(side);
+
+    draw_status(
+        f,
+        app,
+        message,
+        program_signal,
+        crafter_signal,
+        areas,
+        side[0],
+    );
+
+    if macro_rows > 0 {
+        draw_macro(f, app, side[1]);
+    }
+
+    if buff_rows > 0 {
+        draw_buffs(f, &app.theme, &buffs, side[2]);
+    }
+
+    draw_log(f, app, side[3]);
+    areas.log = side[3];
 }
 
 fn draw_settings<B>(f: &mut Frame<B>, app: &App, area: Rect)
@@ -100,47 +494,132 @@ where
     B: Backend,
 {
     // Settings
-    let amount = &app.max_amount.to_string();
-    let food_duration = &app.food_duration.to_string();
-    let macro1_duration = &app.macro1_duration.to_string();
-    let macro2_duration = &app.macro2_duration.to_string();
-    let macro3_duration = &app.macro3_duration.to_string();
+    let profile = &app.profile;
+    let row = |label: &'static str, value: String, field: &str| {
+        Row::new(vec![
+            Cell::from(label),
+            Cell::from(value),
+            Cell::from(profile.source(field).to_string()).style(app.theme.muted),
+        ])
+    };
 
     let mut rows = Vec::new();
-    rows.push(Row::new(vec!["Name:", &app.name]));
-    rows.push(Row::new(vec!["Amount:", amount]));
+    rows.push(Row::new(vec![
+        Cell::from("Name:"),
+        Cell::from(profile.name.clone()),
+    ]));
+    rows.push(row("Amount:", profile.amount.to_string(), "amount"));
+    if profile.mode == Mode::QuickSynthesis {
+        rows.push(row("Mode:", profile.mode.to_string(), "mode"));
+        rows.push(row(
+            "Batch:",
+            profile.quick_synthesis.batch.to_string(),
+            "quick_synthesis",
+        ));
+        rows.push(row(
+            "Item Duration:",
+            profile.quick_synthesis.item_duration.to_string(),
+            "quick_synthesis",
+        ));
+    }
 
-    if !app.food.is_empty() {
-        rows.push(Row::new(vec!["Food:", &app.food]));
-        rows.push(Row::new(vec!["Food Duration:", food_duration]));
+    if !profile.food.is_empty() {
+        rows.push(row("Food:", profile.food.clone(), "food"));
+        rows.push(row(
+            "Food Duration:",
+            profile.food_duration.to_string(),
+            "food_duration",
+        ));
+        if profile.food_stock > 0 {
+            rows.push(row(
+                "Food Stock:",
+                profile.food_stock.to_string(),
+                "food_stock",
+            ));
+        }
     }
 
-    if !app.potion.is_empty() {
-        rows.push(Row::new(vec!["Potion:", &app.potion]));
+    if !profile.potion.is_empty() {
+        rows.push(row("Potion:", profile.potion.clone(), "potion"));
+        if profile.potion_stock > 0 {
+            rows.push(row(
+                "Potion Stock:",
+                profile.potion_stock.to_string(),
+                "potion_stock",
+            ));
+        }
     }
 
-    rows.push(Row::new(vec!["Macro 1:", &app.macro1]));
-    rows.push(Row::new(vec!["Macro 1 Duration:", macro1_duration]));
+    if profile.mode == Mode::Macro {
+        rows.push(row("Macro 1:", profile.macro1.clone(), "macro1"));
+        rows.push(row(
+            "Macro 1 Duration:",
+            profile.macro1_duration.to_string(),
+            "macro1_duration",
+        ));
 
-    if !app.macro2.is_empty() {
-        rows.push(Row::new(vec!["Macro 2:", &app.macro2]));
-        rows.push(Row::new(vec!["Macro 2 Duration:", macro2_duration]));
+        if !profile.macro2.is_empty() {
+            rows.push(row("Macro 2:", profile.macro2.clone(), "macro2"));
+            rows.push(row(
+                "Macro 2 Duration:",
+                profile.macro2_duration.to_string(),
+                "macro2_duration",
+            ));
+        }
+
+        if !profile.macro3.is_empty() {
+            rows.push(row("Macro 3:", profile.macro3.clone(), "macro3"));
+            rows.push(row(
+                "Macro 3 Duration:",
+                profile.macro3_duration.to_string(),
+                "macro3_duration",
+            ));
+        }
+    }
+
+    if !profile.steps.is_empty() {
+        rows.push(row(
+            "Steps:",
+            format!("{} steps", profile.steps.len()),
+            "steps",
+        ));
     }
 
-    if !app.macro3.is_empty() {
-        rows.push(Row::new(vec!["Macro 3:", &app.macro3]));
-        rows.push(Row::new(vec!["Macro 3 Duration:", macro3_duration]));
+    if !profile.script.is_empty() {
+        rows.push(row("Script:", profile.script.clone(), "script"));
     }
 
-    rows.push(Row::new(vec!["Start/Pause:", &app.start_pause]));
-    rows.push(Row::new(vec!["Stop:", &app.stop]));
-    rows.push(Row::new(vec!["Confirm:", &app.confirm]));
-    rows.push(Row::new(vec!["Cancel:", &app.cancel]));
+    rows.push(row(
+        "Start/Pause:",
+        profile.start_pause.clone(),
+        "start_pause",
+    ));
+    rows.push(row("Stop:", profile.stop.clone(), "stop"));
+    rows.push(row("Confirm:", profile.confirm.clone(), "confirm"));
+    rows.push(row("Cancel:", profile.cancel.clone(), "cancel"));
+
+    for (label, presses, field) in [
+        ("Open Synthesis:", &profile.open_synthesis, "open_synthesis"),
+        (
+            "Close Synthesis:",
+            &profile.close_synthesis,
+            "close_synthesis",
+        ),
+    ] {
+        if !presses.is_empty() {
+            let keys: Vec<&str> = presses.iter().map(|press| press.key.as_str()).collect();
+            rows.push(row(label, keys.join(", "), field));
+        }
+    }
 
     let table = Table::new(rows)
-        .style(Style::default().fg(Color::White))
+        .style(app.theme.text)
         .block(Block::default().title("Settings").borders(Borders::ALL))
-        .widths(&[Constraint::Percentage(60), Constraint::Percentage(40)]);
+        .widths(&[
+            Constraint::Percentage(45),
+            Constraint::Percentage(25),
+            Constraint::Percentage(30),
+        ]);
     f.render_widget(table, area);
 }
 
@@ -150,25 +629,18 @@ fn draw_status<B>(
     message: &str,
     program_signal: &Arc<AtomicBool>,
     crafter_signal: &Arc<AtomicBool>,
+    areas: &mut Areas,
     area: Rect,
 ) where
     B: Backend,
 {
     // Status
-    let mut title = String::from("Status: ");
-    let mut block = Block::default()
+    let (name, style) = state(&app.theme, program_signal, crafter_signal);
+    let block = Block::default()
         .borders(Borders::ALL)
-        .border_style(Style::default().add_modifier(Modifier::BOLD));
-    if !program_signal.load(Ordering::Relaxed) {
-        title.push_str("STOPPED");
-        block = block.title(title);
-    } else if crafter_signal.load(Ordering::Relaxed) {
-        title.push_str("CRAFTING");
-        block = block.title(title).style(Style::default().fg(Color::Green));
-    } else {
-        title.push_str("PAUSED");
-        block = block.title(title).style(Style::default().fg(Color::Red));
-    }
+        .border_style(Style::default().add_modifier(Modifier::BOLD))
+        .title(format!("Status: {}", name))
+        .style(style);
     f.render_widget(block, area);
 
     let status = Layout::default()
@@ -180,67 +652,482 @@ fn draw_status<B>(
                 Constraint::Length(1), // Instructions
                 Constraint::Length(1), // Empty
                 Constraint::Length(4), // Progress Gauge
-                Constraint::Length(1), // Empty
+                Constraint::Length(1), // Messages
+                Constraint::Length(3), // Statistics
             ]
             .as_ref(),
         )
         .split(area);
 
-    // Print Instructions
-    let mut instructions_1 = String::from("Press \"");
-    instructions_1.push_str(&app.start_pause);
-    instructions_1.push_str("\" to Start/Pause");
-    f.render_widget(Paragraph::new(instructions_1), status[0]);
+    // Buttons
+    let start_pause = format!(" Start/Pause ({}) ", app.profile.start_pause);
+    let stop = format!(" Stop ({}) ", app.profile.stop);
+    let buttons = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Length(start_pause.chars().count() as u16),
+                Constraint::Length(2),
+                Constraint::Length(stop.chars().count() as u16),
+                Constraint::Min(0),
+            ]
+            .as_ref(),
+        )
+        .split(status[0]);
+    let button = app.theme.button;
+    f.render_widget(Paragraph::new(start_pause).style(button), buttons[0]);
+    f.render_widget(Paragraph::new(stop).style(button), buttons[2]);
+    areas.start_pause = buttons[0];
+    areas.stop = buttons[2];
 
-    let mut instructions_2 = String::from("Press \"");
-    instructions_2.push_str(&app.stop);
-    instructions_2.push_str("\" to Stop");
-    f.render_widget(Paragraph::new(instructions_2), status[1]);
+    // Print Instructions
+    f.render_widget(
+        Paragraph::new("Click a button or press its key").style(app.theme.hint),
+        status[1],
+    );
 
     // Progress Gauge
-    let mut progress: u16;
-    if app.max_amount == 0 {
-        progress = 0;
-    } else {
-        progress = (app.current_amount * 100 / app.max_amount) as u16;
-    }
-    
-    if progress >= 100 {
-        progress = 100;
-    }
+    let progress = progress(app);
 
-    let current_amount = app.current_amount.to_string();
-    let max_amount = app.max_amount.to_string();
+    let current_amount = app.progress.crafted.to_string();
+    let max_amount = app.profile.amount.to_string();
 
-    title = String::from("Crafted: ");
+    let mut title = String::from("Crafted: ");
     title.push_str(&current_amount);
     title.push('/');
     title.push_str(&max_amount);
     let gauge = Gauge::default()
         .block(Block::default().title(title))
-        .gauge_style(
-            Style::default()
-                .fg(Color::LightBlue)
-                .add_modifier(Modifier::ITALIC | Modifier::BOLD),
-        )
+        .gauge_style(app.theme.progress)
         .percent(progress);
     f.render_widget(gauge, status[3]);
 
     // Messages
     f.render_widget(Paragraph::new(message), status[4]);
+
+    // Statistics
+    let stats = match app.estimate() {
+        Some(estimate) => statistics(&estimate),
+        None => vec![Spans::from("No estimate, the run is too long to estimate")],
+    };
+    f.render_widget(Paragraph::new(stats).style(app.theme.hint), status[5]);
+}
+
+/// statistics describes the estimate for the rest of the run
+fn statistics(estimate: &Estimate) -> Vec<Spans<'static>> {
+    let average = if estimate.observed > 0 {
+        format!(
+            "{:.1}s per craft ({} observed)",
+            estimate.per_craft, estimate.observed
+        )
+    } else {
+        format!("{:.1}s per craft (planned)", estimate.per_craft)
+    };
+    let per_hour = if estimate.per_craft > 0.0 {
+        3600.0 / estimate.per_craft
+    } else {
+        0.0
+    };
+    let mut consumption = Vec::new();
+    if let Some(food) = estimate.food {
+        consumption.push(format!("{} food", food));
+    }
+    if let Some(potion) = estimate.potion {
+        consumption.push(format!("{} potion(s)", potion));
+    }
+    if consumption.is_empty() {
+        consumption.push(String::from("none"));
+    }
+
+    vec![
+        Spans::from(format!("Average: {}, {:.1} crafts/hour", average, per_hour)),
+        Spans::from(format!(
+            "Remaining: {}, finishing at {}",
+            format_secs(estimate.remaining),
+            estimate.finish.format("%H:%M:%S")
+        )),
+        Spans::from(format!("Projected use: {}", consumption.join(", "))),
+    ]
+}
+
+/// progress returns the percent of the amount crafted
+fn progress(app: &App) -> u16 {
+    let mut progress: u16;
+    if app.profile.amount == 0 {
+        progress = 0;
+    } else {
+        progress = (app.progress.crafted * 100 / app.profile.amount) as u16;
+    }
+
+    if progress >= 100 {
+        progress = 100;
+    }
+
+    progress
+}
+
+/// format_secs formats seconds as h:mm:ss or m:ss
+fn format_secs(secs: i64) -> String {
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs % 3600 / 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+fn draw_macro<B>(f: &mut Frame<B>, app: &App, area: Rect)
+where
+    B: Backend,
+{
+    // Macro
+    f.render_widget(Block::default().title("Macro").borders(Borders::ALL), area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Length(2), Constraint::Length(2)].as_ref())
+        .split(area);
+
+    let (step, start) = match &app.step {
+        Some(step) => step,
+        None => {
+            f.render_widget(
+                Paragraph::new("No macro running").style(app.theme.muted),
+                rows[0],
+            );
+            return;
+        }
+    };
+
+    let elapsed = (Local::now() - *start).num_seconds().max(0) as u64;
+    let percent = |elapsed: u64, total: u64| {
+        (elapsed * 100)
+            .checked_div(total)
+            .map_or(100, |percent| percent.min(100) as u16)
+    };
+
+    let current = elapsed.min(step.duration);
+    let gauge = Gauge::default()
+        .block(Block::default().title(format!(
+            "Macro {} of {}: {}/{}s",
+            step.index, step.count, current, step.duration
+        )))
+        .gauge_style(app.theme.progress)
+        .percent(percent(current, step.duration));
+    f.render_widget(gauge, rows[0]);
+
+    let chain = (step.elapsed + elapsed).min(step.total);
+    let gauge = Gauge::default()
+        .block(Block::default().title(format!("Craft: {}/{}s", chain, step.total)))
+        .gauge_style(app.theme.chain)
+        .percent(percent(chain, step.total));
+    f.render_widget(gauge, rows[1]);
+}
+
+/// Buff is a consumable tracked by the crafter
+struct Buff {
+    name: &'static str,
+    count: i32,
+    start_time: i64,
+    duration: i64,
+}
+
+/// buffs returns the buffs used by the current profile
+fn buffs(app: &App) -> Vec<Buff> {
+    let mut buffs = Vec::new();
+    if !app.profile.food.is_empty() {
+        buffs.push(Buff {
+            name: "Food",
+            count: app.progress.food,
+            start_time: app.progress.food_start_time,
+            duration: app.profile.food_duration,
+        });
+    }
+    if !app.profile.potion.is_empty() {
+        buffs.push(Buff {
+            name: "Potion",
+            count: app.progress.potion,
+            start_time: app.progress.potion_start_time,
+            duration: POTION_DURATION,
+        });
+    }
+    buffs
+}
+
+/// buff_height returns the height of the buffs pane
+fn buff_height(buffs: usize) -> u16 {
+    if buffs == 0 {
+        0
+    } else {
+        buffs as u16 * 2 + 2
+    }
+}
+
+/// buff_timer returns the seconds left on an active buff, when it will be refreshed,
+/// and the percent and style of its gauge
+fn buff_timer(theme: &Theme, buff: &Buff, now: i64) -> Option<(i64, String, u16, Style)> {
+    if buff.start_time == 0 {
+        return None;
+    }
+
+    // the crafter refreshes the buff before the first craft after it expires
+    let remaining = (buff.start_time + buff.duration - now).max(0);
+    let refresh = Local
+        .timestamp_opt(buff.start_time + buff.duration + 1, 0)
+        .single()
+        .map(|time| time.format("%H:%M:%S").to_string())
+        .unwrap_or_default();
+
+    let percent = if buff.duration > 0 {
+        (remaining * 100 / buff.duration).clamp(0, 100) as u16
+    } else {
+        0
+    };
+    let style = if percent > 50 {
+        theme.buff_high
+    } else if percent > LOW_BUFF {
+        theme.buff_mid
+    } else {
+        theme.buff_low
+    };
+
+    Some((remaining, refresh, percent, style))
+}
+
+/// low_buff marks a buff that is running out, for themes without colour
+fn low_buff(percent: u16) -> &'static str {
+    if percent > LOW_BUFF {
+        ""
+    } else {
+        " (low)"
+    }
+}
+
+fn draw_buffs<B>(f: &mut Frame<B>, theme: &Theme, buffs: &[Buff], area: Rect)
+where
+    B: Backend,
+{
+    // Buffs
+    f.render_widget(Block::default().title("Buffs").borders(Borders::ALL), area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints(vec![Constraint::Length(2); buffs.len()])
+        .split(area);
+
+    let now = Local::now().timestamp();
+    for (buff, row) in buffs.iter().zip(rows) {
+        let mut title = format!("{} ({} used): ", buff.name, buff.count);
+        let (percent, style) = match buff_timer(theme, buff, now) {
+            Some((remaining, refresh, percent, style)) => {
+                title.push_str(&format!(
+                    "{} left{}, next refresh after {}",
+                    format_secs(remaining),
+                    low_buff(percent),
+                    refresh
+                ));
+                (percent, style)
+            }
+            None => {
+                title.push_str("not active");
+                (0, theme.muted)
+            }
+        };
+
+        let gauge = Gauge::default()
+            .block(Block::default().title(title))
+            .gauge_style(style)
+            .percent(percent);
+        f.render_widget(gauge, row);
+    }
+}
+
+fn draw_log<B>(f: &mut Frame<B>, app: &App, area: Rect)
+where
+    B: Backend,
+{
+    // Activity Log
+    let filter = match app.log_filter {
+        Some(kind) => kind.to_string(),
+        None => String::from("all"),
+    };
+    let mut title = format!(
+        "Activity Log [{}] ({}/{} to scroll, {} to filter)",
+        filter,
+        key_label(&app.keys.up),
+        key_label(&app.keys.down),
+        key_label(&app.keys.filter)
+    );
+    if app.log_scroll > 0 {
+        title.push_str(&format!(" -{}", app.log_scroll));
+    }
+
+    // show the newest entries that fit, minus the ones scrolled past
+    let entries = app.log_entries();
+    let height = area.height.saturating_sub(2) as usize;
+    let end = entries.len().saturating_sub(app.log_scroll);
+    let start = end.saturating_sub(height);
+
+    let items: Vec<ListItem> = entries[start..end]
+        .iter()
+        .map(|message| {
+            ListItem::new(Spans::from(vec![
+                Span::styled(
+                    message.time.format("%H:%M:%S ").to_string(),
+                    app.theme.muted,
+                ),
+                Span::styled(
+                    format!("{:<9} ", message.kind.to_string()),
+                    app.theme.log(message.kind),
+                ),
+                Span::raw(message.text.clone()),
+                Span::styled(
+                    format!("  {}/{}", message.crafted, app.profile.amount),
+                    app.theme.muted,
+                ),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items).block(Block::default().title(title).borders(Borders::ALL));
+    f.render_widget(list, area);
+}
+
+// Profiles Tab
+pub fn ui_profiles<B>(f: &mut Frame<B>, app: &App, area: Rect)
+where
+    B: Backend,
+{
+    let chunks = Layout::default()
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+        .direction(Direction::Horizontal)
+        .split(area);
+
+    let items: Vec<ListItem> = app
+        .profiles
+        .iter()
+        .map(|config| {
+            let marker = if config.id == app.profile.id {
+                "*"
+            } else {
+                " "
+            };
+            ListItem::new(format!(
+                "{} {:>3}  {}  x{}",
+                marker, config.id, config.name, config.amount
+            ))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default().title("Profiles").borders(Borders::ALL))
+        .highlight_style(app.theme.highlight)
+        .highlight_symbol("> ");
+    let mut state = ListState::default();
+    if !app.profiles.is_empty() {
+        state.select(Some(app.profile_index));
+    }
+    f.render_stateful_widget(list, chunks[0], &mut state);
+
+    let side = Layout::default()
+        .constraints(
+            [
+                Constraint::Length(7), // Instructions
+                Constraint::Min(0),    // Share String
+                Constraint::Length(3), // Import Input
+            ]
+            .as_ref(),
+        )
+        .direction(Direction::Vertical)
+        .split(chunks[1]);
+
+    let keys = &app.keys;
+    let mut instructions = vec![
+        Spans::from(format!(
+            "{}/{} to select, {} to use",
+            key_label(&keys.up),
+            key_label(&keys.down),
+            key_label(&keys.select)
+        )),
+        Spans::from(format!(
+            "{} to export as a share string",
+            key_label(&keys.export)
+        )),
+        Spans::from(format!(
+            "{} to export to a file",
+            key_label(&keys.export_file)
+        )),
+        Spans::from(format!(
+            "{} to import a share string or file",
+            key_label(&keys.import)
+        )),
+    ];
+    if let Some(status) = &app.profile_status {
+        instructions.push(Spans::from(Span::styled(status.as_str(), app.theme.status)));
+    }
+    f.render_widget(
+        Paragraph::new(instructions).block(Block::default().title("Actions").borders(Borders::ALL)),
+        side[0],
+    );
+
+    let share = app.share.as_deref().unwrap_or("");
+    f.render_widget(
+        Paragraph::new(share)
+            .block(Block::default().title("Share String").borders(Borders::ALL))
+            .wrap(Wrap { trim: false }),
+        side[1],
+    );
+
+    let (title, input, style) = match &app.input {
+        Some(input) => (
+            "Import (Enter to import, Esc to cancel)",
+            input.as_str(),
+            app.theme.status,
+        ),
+        None => ("Import", "", Style::default()),
+    };
+    f.render_widget(
+        Paragraph::new(input)
+            .block(
+                Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+                    .border_style(style),
+            )
+            .scroll((
+                0,
+                (input.len() as u16).saturating_sub(side[2].width.saturating_sub(3)),
+            )),
+        side[2],
+    );
 }
 
 // Config Tab
-pub fn ui_config<B>(f: &mut Frame<B>, app: &App, area: Rect)
+pub fn ui_config<B>(f: &mut Frame<B>, app: &App, areas: &mut Areas, area: Rect)
 where
     B: Backend,
 {
     let chunks = Layout::default()
-        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)].as_ref())
         .direction(Direction::Vertical)
         .split(area);
+    let top = Layout::default()
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .direction(Direction::Horizontal)
+        .split(chunks[0]);
 
-    let home = vec![Spans::from(app.config.display().to_string())];
+    let mut home = vec![Spans::from(app.store.path().display().to_string())];
+    if let Some(error) = &app.error {
+        home.push(Spans::from(""));
+        home.push(Spans::from(Span::styled(
+            format!(
+                "Unable to load config, keeping the last good values: {}",
+                error
+            ),
+            app.theme.error,
+        )));
+    }
     let content = Paragraph::new(home)
         .block(
             Block::default()
@@ -248,18 +1135,123 @@ where
                 .borders(Borders::ALL),
         )
         .wrap(Wrap { trim: true });
-    f.render_widget(content, chunks[0]);
+    f.render_widget(content, top[0]);
 
-    let file = fs::read_to_string(&app.config).expect("Error reading file");
+    draw_backups(f, app, top[1]);
+
+    let text: Vec<Spans> = app
+        .view
+        .iter()
+        .skip(app.config_scroll as usize)
+        .take(chunks[1].height as usize)
+        .map(|line| config_line(&app.theme, line))
+        .collect();
+    let status_content = Paragraph::new(text).block(
+        Block::default()
+            .title(format!(
+                "Config Contents ({}/{} to scroll)",
+                key_label(&app.keys.page_up),
+                key_label(&app.keys.page_down)
+            ))
+            .borders(Borders::ALL),
+    );
+
+    f.render_widget(status_content, chunks[1]);
+    areas.config = chunks[1];
+}
+
+/// config_line highlights a line of the config view
+fn config_line<'a>(theme: &Theme, line: &'a Line) -> Spans<'a> {
+    // the gutter marks invalid fields and the active profile
+    let mut spans = vec![if line.error.is_some() {
+        Span::styled("! ", theme.error.add_modifier(Modifier::BOLD))
+    } else if line.active {
+        Span::styled("> ", theme.highlight)
+    } else {
+        Span::raw("  ")
+    }];
+    spans.push(Span::raw("  ".repeat(line.indent)));
+
+    for token in &line.tokens {
+        spans.push(match token {
+            Token::Key(text) => {
+                let mut style = theme.key;
+                if line.error.is_some() {
+                    style = theme.error.add_modifier(Modifier::UNDERLINED);
+                }
+                Span::styled(text.as_str(), style)
+            }
+            Token::String(text) => Span::styled(text.as_str(), theme.string),
+            Token::Number(text) => Span::styled(text.as_str(), theme.number),
+            Token::Literal(text) => Span::styled(text.as_str(), theme.literal),
+            Token::Punct(text) => Span::raw(text.as_str()),
+            Token::Comment(text) => {
+                let mut style = theme.muted;
+                if line.indent == 0 {
+                    style = style.add_modifier(Modifier::BOLD);
+                }
+                if line.active && line.indent == 0 {
+                    style = theme.highlight;
+                }
+                Span::styled(text.as_str(), style)
+            }
+        });
+    }
 
-    let status = vec![Spans::from(file)];
-    let status_content = Paragraph::new(status)
+    if let Some(error) = &line.error {
+        spans.push(Span::styled(format!("  <- {}", error), theme.error));
+    }
+
+    Spans::from(spans)
+}
+
+fn draw_backups<B>(f: &mut Frame<B>, app: &App, area: Rect)
+where
+    B: Backend,
+{
+    let chunks = Layout::default()
+        .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+        .direction(Direction::Vertical)
+        .split(area);
+
+    let items: Vec<ListItem> = app
+        .backups
+        .iter()
+        .map(|backup| {
+            let name = backup
+                .original
+                .strip_prefix(app.store.path())
+                .unwrap_or(&backup.original)
+                .display()
+                .to_string();
+            ListItem::new(format!(
+                "{}  {}",
+                backup.time.format("%Y-%m-%d %H:%M:%S"),
+                name
+            ))
+        })
+        .collect();
+
+    let list = List::new(items)
         .block(
             Block::default()
-                .title("Config Contents")
+                .title(format!(
+                    "Backups ({}/{} to select, {} to restore)",
+                    key_label(&app.keys.up),
+                    key_label(&app.keys.down),
+                    key_label(&app.keys.restore)
+                ))
                 .borders(Borders::ALL),
         )
-        .wrap(Wrap { trim: true });
+        .highlight_style(app.theme.highlight)
+        .highlight_symbol("> ");
+    let mut state = ListState::default();
+    if !app.backups.is_empty() {
+        state.select(Some(app.backup_index));
+    }
+    f.render_stateful_widget(list, chunks[0], &mut state);
 
-    f.render_widget(status_content, chunks[1]);
+    if let Some(status) = &app.backup_status {
+        f.render_widget(Paragraph::new(status.as_str()), chunks[1]);
+    }
 }
@@ -2,30 +2,6 @@ use crossterm::event::KeyCode;
 
 use enigo::Key;
 
-use serde::{Deserialize, Serialize};
-
-/// Config file
-#[derive(Serialize, Deserialize, Clone)]
-pub struct Config {
-    pub id: i32,
-    pub last_used: bool,
-    pub name: String,
-    pub amount: i32,
-    pub food: String,
-    pub food_duration: i64,
-    pub potion: String,
-    pub macro1: String,
-    pub macro1_duration: u64,
-    pub macro2: String,
-    pub macro2_duration: u64,
-    pub macro3: String,
-    pub macro3_duration: u64,
-    pub start_pause: String,
-    pub stop: String,
-    pub confirm: String,
-    pub cancel: String,
-}
-
 pub fn get_crossterm_key_code(key: &str) -> Option<KeyCode> {
     let key = key.to_lowercase();
     if key.len() == 1 {
@@ -38,10 +14,24 @@ pub fn get_crossterm_key_code(key: &str) -> Option<KeyCode> {
         "pageup" => Some(KeyCode::PageUp),
         "pagedown" => Some(KeyCode::PageDown),
         "delete" => Some(KeyCode::Delete),
+        "left" => Some(KeyCode::Left),
+        "right" => Some(KeyCode::Right),
+        "up" => Some(KeyCode::Up),
+        "down" => Some(KeyCode::Down),
+        "enter" => Some(KeyCode::Enter),
+        "tab" => Some(KeyCode::Tab),
+        "backspace" => Some(KeyCode::Backspace),
+        "esc" => Some(KeyCode::Esc),
+        "space" => Some(KeyCode::Char(' ')),
         _ => None,
     }
 }
 
+/// is_key checks whether a key code is the key named in the config
+pub fn is_key(code: KeyCode, key: &str) -> bool {
+    get_crossterm_key_code(key) == Some(code)
+}
+
 pub fn get_enigo_key_code(key: &str) -> Option<Key> {
     let key = key.to_lowercase();
     if key.len() == 1 {
@@ -54,6 +44,53 @@ pub fn get_enigo_key_code(key: &str) -> Option<Key> {
         "pageup" => Some(Key::PageUp),
         "pagedown" => Some(Key::PageDown),
         "delete" => Some(Key::Delete),
-        _ => None,
+        "left" => Some(Key::LeftArrow),
+        "right" => Some(Key::RightArrow),
+        "up" => Some(Key::UpArrow),
+        "down" => Some(Key::DownArrow),
+        "enter" => Some(Key::Return),
+        "tab" => Some(Key::Tab),
+        "backspace" => Some(Key::Backspace),
+        "esc" => Some(Key::Escape),
+        "space" => Some(Key::Space),
+        _ => key
+            .strip_prefix("numpad")
+            .and_then(|digit| digit.parse::<usize>().ok())
+            .and_then(get_numpad_key),
     }
 }
+
+/// get_numpad_key returns the numpad key for a digit, which enigo only names on Windows
+#[cfg(target_os = "windows")]
+fn get_numpad_key(digit: usize) -> Option<Key> {
+    [
+        Key::Numpad0,
+        Key::Numpad1,
+        Key::Numpad2,
+        Key::Numpad3,
+        Key::Numpad4,
+        Key::Numpad5,
+        Key::Numpad6,
+        Key::Numpad7,
+        Key::Numpad8,
+        Key::Numpad9,
+    ]
+    .get(digit)
+    .copied()
+}
+
+/// get_numpad_key returns the numpad key for a digit as an X keycode
+#[cfg(target_os = "linux")]
+fn get_numpad_key(digit: usize) -> Option<Key> {
+    [90, 87, 88, 89, 83, 84, 85, 79, 80, 81]
+        .get(digit)
+        .map(|code| Key::Raw(*code))
+}
+
+/// get_numpad_key returns the numpad key for a digit as a macOS virtual keycode
+#[cfg(target_os = "macos")]
+fn get_numpad_key(digit: usize) -> Option<Key> {
+    [0x52, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5b, 0x5c]
+        .get(digit)
+        .map(|code| Key::Raw(*code))
+}
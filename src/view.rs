@@ -0,0 +1,255 @@
+use crate::config::{self, Config, Store};
+
+use serde_json::Value;
+
+use std::{fs, path::Path};
+
+/// Token is a highlighted part of a line in the config view
+#[derive(Clone)]
+pub enum Token {
+    Key(String),
+    String(String),
+    Number(String),
+    Literal(String),
+    Punct(String),
+    Comment(String),
+}
+
+/// Line is a line of the config view
+#[derive(Clone)]
+pub struct Line {
+    pub indent: usize,
+    pub tokens: Vec<Token>,
+    /// whether the line belongs to the active profile
+    pub active: bool,
+    /// validation error of the field on this line
+    pub error: Option<String>,
+}
+
+/// Section is a file, or a profile of a single-file config, being added to the view
+struct Section<'a> {
+    active: bool,
+    /// validation errors as (field path, message)
+    errors: Vec<(String, String)>,
+    /// field paths found in the file
+    found: Vec<String>,
+    lines: &'a mut Vec<Line>,
+}
+
+/// lines builds the config view, highlighting the active profile and marking the
+/// fields that failed validation
+pub fn lines(store: &Store, configs: &[Config], active: i32) -> Vec<Line> {
+    let mut lines = Vec::new();
+
+    match store {
+        Store::File(path) => match read(path) {
+            Ok(Value::Array(profiles)) => {
+                for profile in &profiles {
+                    let id = profile
+                        .get("id")
+                        .and_then(Value::as_i64)
+                        .unwrap_or_default() as i32;
+                    let name = profile.get("name").and_then(Value::as_str).unwrap_or("");
+                    let header = format!("# profile {} {}", id, name);
+                    add(
+                        &mut lines,
+                        &header,
+                        profile,
+                        id == active,
+                        errors(configs, id),
+                    );
+                }
+            }
+            Ok(value) => add(&mut lines, "# config", &value, false, Vec::new()),
+            Err(e) => unreadable(&mut lines, "# config", e),
+        },
+        Store::Dir(root) => {
+            let settings = store.settings_path();
+            if settings.exists() {
+                let errors = store
+                    .load_settings()
+                    .map(|settings| settings.keys.validate())
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|(name, message)| (format!("keys.{}", name), message))
+                    .collect();
+                let header = format!("# {}", config::SETTINGS_FILE);
+                match read(&settings) {
+                    Ok(value) => add(&mut lines, &header, &value, false, errors),
+                    Err(e) => unreadable(&mut lines, &header, e),
+                }
+            }
+
+            for (id, path, _) in store.load_profiles().unwrap_or_default() {
+                let name = path.strip_prefix(root).unwrap_or(&path).display();
+                let mut header = format!("# {}", name);
+                if id == active {
+                    header.push_str(" (active)");
+                }
+                match read(&path) {
+                    Ok(value) => add(
+                        &mut lines,
+                        &header,
+                        &value,
+                        id == active,
+                        errors(configs, id),
+                    ),
+                    Err(e) => unreadable(&mut lines, &header, e),
+                }
+            }
+        }
+    }
+
+    lines
+}
+
+/// active_line returns the first line of the active profile
+pub fn active_line(lines: &[Line]) -> Option<usize> {
+    lines.iter().position(|line| line.active)
+}
+
+/// read parses a JSON file
+fn read(path: &Path) -> Result<Value, String> {
+    let file = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&file).map_err(|e| e.to_string())
+}
+
+/// errors returns the validation errors of a profile as (field path, message)
+fn errors(configs: &[Config], id: i32) -> Vec<(String, String)> {
+    configs
+        .iter()
+        .find(|config| config.id == id)
+        .map(config::validate)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(field, message)| (field.to_string(), message))
+        .collect()
+}
+
+/// add adds a file or profile to the view, followed by the errors of fields it does not set
+fn add(
+    lines: &mut Vec<Line>,
+    header: &str,
+    value: &Value,
+    active: bool,
+    errors: Vec<(String, String)>,
+) {
+    lines.push(Line {
+        indent: 0,
+        tokens: vec![Token::Comment(header.to_string())],
+        active,
+        error: None,
+    });
+
+    let mut section = Section {
+        active,
+        errors,
+        found: Vec::new(),
+        lines,
+    };
+    section.push(value, None, "", 0, false);
+
+    // errors of fields inherited from the settings
+    let missing: Vec<(String, String)> = section
+        .errors
+        .iter()
+        .filter(|(field, _)| !section.found.contains(field))
+        .cloned()
+        .collect();
+    for (field, message) in missing {
+        section.lines.push(Line {
+            indent: 1,
+            tokens: vec![Token::Comment(format!("{} (inherited)", field))],
+            active,
+            error: Some(message),
+        });
+    }
+
+    section.lines.push(Line {
+        indent: 0,
+        tokens: Vec::new(),
+        active: false,
+        error: None,
+    });
+}
+
+/// unreadable adds a file that could not be parsed to the view
+fn unreadable(lines: &mut Vec<Line>, header: &str, error: String) {
+    lines.push(Line {
+        indent: 0,
+        tokens: vec![Token::Comment(header.to_string())],
+        active: false,
+        error: Some(error),
+    });
+    lines.push(Line {
+        indent: 0,
+        tokens: Vec::new(),
+        active: false,
+        error: None,
+    });
+}
+
+impl<'a> Section<'a> {
+    /// push adds a value and its children as formatted JSON
+    fn push(&mut self, value: &Value, key: Option<&str>, parent: &str, indent: usize, comma: bool) {
+        let path = match key {
+            Some(key) if parent.is_empty() => key.to_string(),
+            Some(key) => format!("{}.{}", parent, key),
+            None => parent.to_string(),
+        };
+
+        let mut tokens = Vec::new();
+        if let Some(key) = key {
+            tokens.push(Token::Key(Value::from(key).to_string()));
+            tokens.push(Token::Punct(String::from(": ")));
+            self.found.push(path.clone());
+        }
+        let error = self
+            .errors
+            .iter()
+            .find(|(field, _)| key.is_some() && *field == path)
+            .map(|(_, message)| message.clone());
+        let close = if comma { "," } else { "" };
+
+        match value {
+            Value::Object(map) if !map.is_empty() => {
+                tokens.push(Token::Punct(String::from("{")));
+                self.line(indent, tokens, error);
+                for (i, (key, value)) in map.iter().enumerate() {
+                    self.push(value, Some(key), &path, indent + 1, i + 1 < map.len());
+                }
+                self.line(indent, vec![Token::Punct(format!("}}{}", close))], None);
+            }
+            Value::Array(items) if !items.is_empty() => {
+                tokens.push(Token::Punct(String::from("[")));
+                self.line(indent, tokens, error);
+                for (i, item) in items.iter().enumerate() {
+                    self.push(item, None, &path, indent + 1, i + 1 < items.len());
+                }
+                self.line(indent, vec![Token::Punct(format!("]{}", close))], None);
+            }
+            _ => {
+                tokens.push(match value {
+                    Value::String(_) => Token::String(value.to_string()),
+                    Value::Number(_) => Token::Number(value.to_string()),
+                    Value::Object(_) => Token::Punct(String::from("{}")),
+                    Value::Array(_) => Token::Punct(String::from("[]")),
+                    _ => Token::Literal(value.to_string()),
+                });
+                if comma {
+                    tokens.push(Token::Punct(String::from(",")));
+                }
+                self.line(indent, tokens, error);
+            }
+        }
+    }
+
+    fn line(&mut self, indent: usize, tokens: Vec<Token>, error: Option<String>) {
+        self.lines.push(Line {
+            indent,
+            tokens,
+            active: self.active,
+            error,
+        });
+    }
+}